@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use my_no_sql_core::{AnyExpirationIndex, ExpirationIndexKind};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+const ITEMS_AMOUNT: [usize; 2] = [10_000, 100_000];
+
+fn get_kinds() -> [(&'static str, ExpirationIndexKind); 2] {
+    [
+        ("btree", ExpirationIndexKind::BTree),
+        (
+            "bucketed",
+            ExpirationIndexKind::Bucketed {
+                resolution: Duration::from_secs(1),
+            },
+        ),
+    ]
+}
+
+// Every item has its own microsecond expiration moment
+fn create_items(amount: usize) -> Vec<(DateTimeAsMicroseconds, String)> {
+    (0..amount)
+        .map(|i| {
+            (
+                DateTimeAsMicroseconds::new(i as i64 * 997),
+                format!("row-{}", i),
+            )
+        })
+        .collect()
+}
+
+// A thousand items expire at the same moment
+fn create_items_with_same_expiration(amount: usize) -> Vec<(DateTimeAsMicroseconds, String)> {
+    (0..amount)
+        .map(|i| {
+            (
+                DateTimeAsMicroseconds::new((i / 1000) as i64 * 1_000_000),
+                format!("row-{}", i),
+            )
+        })
+        .collect()
+}
+
+fn fill_index(
+    kind: ExpirationIndexKind,
+    items: &[(DateTimeAsMicroseconds, String)],
+) -> AnyExpirationIndex<String> {
+    let mut index = AnyExpirationIndex::new(kind);
+
+    for (expires, item) in items {
+        index.add(Some(*expires), item);
+    }
+
+    index
+}
+
+fn bench_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("expiration_index_add");
+
+    for amount in ITEMS_AMOUNT {
+        let items = create_items(amount);
+
+        for (name, kind) in get_kinds() {
+            group.bench_with_input(BenchmarkId::new(name, amount), &items, |b, items| {
+                b.iter(|| fill_index(kind, items))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_remove(c: &mut Criterion) {
+    bench_remove_items(c, "expiration_index_remove", create_items);
+    bench_remove_items(
+        c,
+        "expiration_index_remove_same_expiration",
+        create_items_with_same_expiration,
+    );
+}
+
+fn bench_remove_items(
+    c: &mut Criterion,
+    group_name: &str,
+    create_items: fn(usize) -> Vec<(DateTimeAsMicroseconds, String)>,
+) {
+    let mut group = c.benchmark_group(group_name);
+
+    for amount in ITEMS_AMOUNT {
+        let items = create_items(amount);
+
+        for (name, kind) in get_kinds() {
+            group.bench_with_input(BenchmarkId::new(name, amount), &items, |b, items| {
+                b.iter_batched(
+                    || fill_index(kind, items),
+                    |mut index| {
+                        for (expires, item) in items {
+                            index.remove(Some(*expires), item);
+                        }
+                        index
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_get_items_to_expire(c: &mut Criterion) {
+    let mut group = c.benchmark_group("expiration_index_get_items_to_expire");
+
+    for amount in ITEMS_AMOUNT {
+        let items = create_items(amount);
+        let now = items[amount / 10].0;
+
+        for (name, kind) in get_kinds() {
+            let index = fill_index(kind, &items);
+
+            group.bench_function(BenchmarkId::new(name, amount), |b| {
+                b.iter(|| index.get_items_to_expire(now))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_add, bench_remove, bench_get_items_to_expire);
+criterion_main!(benches);
@@ -0,0 +1,442 @@
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+use rust_extensions::{date_time::DateTimeAsMicroseconds, lazy::LazyVec};
+
+pub trait ExpirationItemKey {
+    fn get_expiration_key(&self) -> &str;
+}
+
+// Items are found inside the bucket by (expiration moment, key of the item).
+// The key is borrowed from the item itself, so adding an item does not allocate a copy of its key
+trait BucketItemKey {
+    fn get_bucket_item_key(&self) -> (i64, &str);
+}
+
+struct BucketItem<T: ExpirationItemKey> {
+    expire_moment: i64,
+    item: T,
+}
+
+impl<T: ExpirationItemKey> BucketItemKey for BucketItem<T> {
+    fn get_bucket_item_key(&self) -> (i64, &str) {
+        (self.expire_moment, self.item.get_expiration_key())
+    }
+}
+
+impl BucketItemKey for (i64, &str) {
+    fn get_bucket_item_key(&self) -> (i64, &str) {
+        (self.0, self.1)
+    }
+}
+
+impl<'s> Hash for dyn BucketItemKey + 's {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_bucket_item_key().hash(state);
+    }
+}
+
+impl<'s> PartialEq for dyn BucketItemKey + 's {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_bucket_item_key() == other.get_bucket_item_key()
+    }
+}
+
+impl<'s> Eq for dyn BucketItemKey + 's {}
+
+impl<T: ExpirationItemKey> Hash for BucketItem<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_bucket_item_key().hash(state);
+    }
+}
+
+impl<T: ExpirationItemKey> PartialEq for BucketItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_bucket_item_key() == other.get_bucket_item_key()
+    }
+}
+
+impl<T: ExpirationItemKey> Eq for BucketItem<T> {}
+
+impl<'s, T: ExpirationItemKey + 's> Borrow<dyn BucketItemKey + 's> for BucketItem<T> {
+    fn borrow(&self) -> &(dyn BucketItemKey + 's) {
+        self
+    }
+}
+
+// Flat coarse buckets of `resolution` microseconds kept in a BTreeMap - it is not a hierarchical
+// timing wheel. Finding a bucket is O(log buckets), expired buckets are taken from the front
+// whole, only the bucket of `now` is scanned item by item. Inside the bucket items are found
+// by the hash, so removal does not depend on how many items expire at the same time
+pub struct BucketedExpirationIndex<T: Clone + ExpirationItemKey> {
+    index: BTreeMap<i64, HashSet<BucketItem<T>>>,
+    resolution: i64,
+    amount: usize,
+}
+
+impl<T: Clone + ExpirationItemKey> BucketedExpirationIndex<T> {
+    pub fn new(resolution: Duration) -> Self {
+        Self {
+            index: BTreeMap::new(),
+            resolution: (resolution.as_micros() as i64).max(1),
+            amount: 0,
+        }
+    }
+
+    fn get_bucket(&self, expire_moment: i64) -> i64 {
+        expire_moment.div_euclid(self.resolution)
+    }
+
+    /// Adding the same item with the same expiration moment twice keeps one copy of it
+    pub fn add(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) {
+        if expiration_moment.is_none() {
+            return;
+        }
+
+        let expire_moment = expiration_moment.unwrap().unix_microseconds;
+        let bucket = self.get_bucket(expire_moment);
+
+        let bucket_item = BucketItem {
+            expire_moment,
+            item: item.clone(),
+        };
+
+        let replaced = self.index.entry(bucket).or_default().replace(bucket_item);
+
+        if replaced.is_none() {
+            self.amount += 1;
+        }
+    }
+
+    /// Returns true if the item was found and removed
+    pub fn remove(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) -> bool {
+        if expiration_moment.is_none() {
+            return false;
+        }
+
+        let expire_moment = expiration_moment.unwrap().unix_microseconds;
+        let bucket = self.get_bucket(expire_moment);
+
+        let items = match self.index.get_mut(&bucket) {
+            Some(items) => items,
+            None => return false,
+        };
+
+        let key = (expire_moment, item.get_expiration_key());
+
+        if !items.remove(&key as &dyn BucketItemKey) {
+            return false;
+        }
+
+        if items.is_empty() {
+            self.index.remove(&bucket);
+        }
+
+        self.amount -= 1;
+        true
+    }
+
+    pub fn update(
+        &mut self,
+        old_expiration_moment: Option<DateTimeAsMicroseconds>,
+        new_expiration_moment: Option<DateTimeAsMicroseconds>,
+        item: &T,
+    ) {
+        self.remove(old_expiration_moment, item);
+        self.add(new_expiration_moment, item);
+    }
+
+    fn get_expired(&self, now: DateTimeAsMicroseconds) -> Vec<(i64, &T)> {
+        let now_bucket = self.get_bucket(now.unix_microseconds);
+
+        let mut result = Vec::new();
+
+        for items in self.index.range(..=now_bucket).map(|(_, items)| items) {
+            let from = result.len();
+
+            for bucket_item in items {
+                if bucket_item.expire_moment <= now.unix_microseconds {
+                    result.push((bucket_item.expire_moment, &bucket_item.item));
+                }
+            }
+
+            result[from..].sort_by_key(|(expire_moment, _)| *expire_moment);
+        }
+
+        result
+    }
+
+    pub fn get_items_to_expire(&self, now: DateTimeAsMicroseconds) -> Option<Vec<&T>> {
+        let mut result = LazyVec::new();
+
+        for (_, item) in self.get_expired(now) {
+            result.add(item);
+        }
+
+        result.get_result()
+    }
+
+    pub fn get_items_to_expire_cloned(&self, now: DateTimeAsMicroseconds) -> Option<Vec<T>> {
+        let mut result = LazyVec::new();
+
+        for (_, item) in self.get_expired(now) {
+            result.add(item.clone());
+        }
+
+        result.get_result()
+    }
+
+    /// Removes the expired items from the index and returns them
+    pub fn drain_items_to_expire(&mut self, now: DateTimeAsMicroseconds) -> Option<Vec<T>> {
+        let now_bucket = self.get_bucket(now.unix_microseconds);
+
+        let mut expired = Vec::new();
+
+        while let Some(mut entry) = self.index.first_entry() {
+            if *entry.key() < now_bucket {
+                expired.extend(
+                    entry
+                        .remove()
+                        .into_iter()
+                        .map(|bucket_item| (bucket_item.expire_moment, bucket_item.item)),
+                );
+                continue;
+            }
+
+            if *entry.key() == now_bucket {
+                let items = entry.get_mut();
+
+                let (expired_items, items_left): (HashSet<_>, HashSet<_>) = std::mem::take(items)
+                    .into_iter()
+                    .partition(|bucket_item| bucket_item.expire_moment <= now.unix_microseconds);
+
+                *items = items_left;
+
+                expired.extend(
+                    expired_items
+                        .into_iter()
+                        .map(|bucket_item| (bucket_item.expire_moment, bucket_item.item)),
+                );
+
+                if items.is_empty() {
+                    entry.remove();
+                }
+            }
+
+            break;
+        }
+
+        if expired.is_empty() {
+            return None;
+        }
+
+        self.amount -= expired.len();
+
+        expired.sort_by_key(|(expire_moment, _)| *expire_moment);
+
+        Some(expired.into_iter().map(|(_, item)| item).collect())
+    }
+
+    pub fn has_data_with_expiration_moment(&self, expiration_moment: i64) -> bool {
+        match self.index.get(&self.get_bucket(expiration_moment)) {
+            Some(items) => items
+                .iter()
+                .any(|bucket_item| bucket_item.expire_moment == expiration_moment),
+            None => false,
+        }
+    }
+
+    pub fn get_earliest_expiration_moment(&self) -> Option<DateTimeAsMicroseconds> {
+        let (_, items) = self.index.first_key_value()?;
+        let expiration_moment = items
+            .iter()
+            .map(|bucket_item| bucket_item.expire_moment)
+            .min()?;
+        Some(DateTimeAsMicroseconds::new(expiration_moment))
+    }
+
+    pub fn len(&self) -> usize {
+        self.amount
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.amount = 0;
+    }
+
+    /// Panics if the internal state of the index is inconsistent
+    pub fn debug_validate(&self) {
+        let mut amount = 0;
+
+        for (bucket, items) in &self.index {
+            assert!(!items.is_empty(), "Empty bucket {}", bucket);
+
+            for bucket_item in items {
+                assert_eq!(
+                    *bucket,
+                    self.get_bucket(bucket_item.expire_moment),
+                    "Item {} is in the wrong bucket",
+                    bucket_item.item.get_expiration_key()
+                );
+            }
+
+            amount += items.len();
+        }
+
+        assert_eq!(
+            amount, self.amount,
+            "Amount of items does not match the index"
+        );
+    }
+}
+
+impl ExpirationItemKey for String {
+    fn get_expiration_key(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, time::Duration};
+
+    use proptest::prelude::*;
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use super::BucketedExpirationIndex;
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Add(Option<i64>, String),
+        Remove(Option<i64>, String),
+        Update(Option<i64>, Option<i64>, String),
+        Drain(i64),
+        Clear,
+    }
+
+    fn to_moment(value: Option<i64>) -> Option<DateTimeAsMicroseconds> {
+        value.map(DateTimeAsMicroseconds::new)
+    }
+
+    fn operation_strategy() -> impl Strategy<Value = Operation> {
+        let moment = proptest::option::of(-10i64..30);
+        let item = "[a-d]";
+
+        prop_oneof![
+            4 => (moment.clone(), item).prop_map(|(moment, item)| Operation::Add(moment, item)),
+            3 => (moment.clone(), item)
+                .prop_map(|(moment, item)| Operation::Remove(moment, item)),
+            3 => (moment.clone(), moment, item)
+                .prop_map(|(old, new, item)| Operation::Update(old, new, item)),
+            1 => (-11i64..31).prop_map(Operation::Drain),
+            1 => Just(Operation::Clear),
+        ]
+    }
+
+    #[test]
+    fn test_items_are_returned_in_expiration_order() {
+        let mut index = BucketedExpirationIndex::new(Duration::from_micros(10));
+
+        index.add(Some(DateTimeAsMicroseconds::new(15)), &"b".to_string());
+        index.add(Some(DateTimeAsMicroseconds::new(3)), &"c".to_string());
+        index.add(Some(DateTimeAsMicroseconds::new(12)), &"a".to_string());
+        index.add(Some(DateTimeAsMicroseconds::new(25)), &"d".to_string());
+
+        let result = index
+            .get_items_to_expire_cloned(DateTimeAsMicroseconds::new(15))
+            .unwrap();
+
+        assert_eq!(vec!["c", "a", "b"], result);
+        assert!(index.has_data_with_expiration_moment(12));
+        assert!(!index.has_data_with_expiration_moment(13));
+    }
+
+    proptest! {
+        #[test]
+        fn test_index_matches_model(
+            resolution in 1u64..8,
+            operations in proptest::collection::vec(operation_strategy(), 0..64),
+        ) {
+            let mut index = BucketedExpirationIndex::new(Duration::from_micros(resolution));
+            let mut model = BTreeSet::new();
+
+            for operation in operations {
+                match &operation {
+                    Operation::Add(moment, item) => {
+                        index.add(to_moment(*moment), item);
+                        if let Some(moment) = moment {
+                            model.insert((*moment, item.clone()));
+                        }
+                    }
+                    Operation::Remove(moment, item) => {
+                        let removed = index.remove(to_moment(*moment), item);
+                        let expected = match moment {
+                            Some(moment) => model.remove(&(*moment, item.clone())),
+                            None => false,
+                        };
+                        prop_assert_eq!(expected, removed);
+                    }
+                    Operation::Update(old, new, item) => {
+                        index.update(to_moment(*old), to_moment(*new), item);
+                        if let Some(old) = old {
+                            model.remove(&(*old, item.clone()));
+                        }
+                        if let Some(new) = new {
+                            model.insert((*new, item.clone()));
+                        }
+                    }
+                    Operation::Drain(now) => {
+                        let mut drained = index
+                            .drain_items_to_expire(DateTimeAsMicroseconds::new(*now))
+                            .unwrap_or_default();
+                        drained.sort();
+
+                        let mut expected: Vec<String> = model
+                            .iter()
+                            .filter(|(moment, _)| *moment <= *now)
+                            .map(|(_, item)| item.clone())
+                            .collect();
+                        expected.sort();
+
+                        model.retain(|(moment, _)| *moment > *now);
+                        prop_assert_eq!(expected, drained);
+                    }
+                    Operation::Clear => {
+                        index.clear();
+                        model.clear();
+                    }
+                }
+
+                index.debug_validate();
+                prop_assert_eq!(model.len(), index.len());
+
+                for now in -11..31 {
+                    let expected: Vec<(i64, String)> = model
+                        .iter()
+                        .filter(|(moment, _)| *moment <= now)
+                        .cloned()
+                        .collect();
+
+                    let mut actual: Vec<(i64, String)> = index
+                        .get_expired(DateTimeAsMicroseconds::new(now))
+                        .into_iter()
+                        .map(|(moment, item)| (moment, item.clone()))
+                        .collect();
+
+                    prop_assert!(actual.windows(2).all(|itm| itm[0].0 <= itm[1].0));
+                    actual.sort();
+
+                    prop_assert_eq!(expected, actual);
+                }
+            }
+        }
+    }
+}
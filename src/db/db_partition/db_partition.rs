@@ -3,12 +3,14 @@ use rust_extensions::lazy::LazyVec;
 
 #[cfg(feature = "master-node")]
 use rust_extensions::date_time::AtomicDateTimeAsMicroseconds;
+#[cfg(feature = "master-node")]
+use std::{collections::HashSet, sync::Mutex, time::Duration};
 
-use crate::db::DbRow;
+use crate::{db::DbRow, db_filter::FilterExpression};
 
-use std::{collections::btree_map::Values, sync::Arc};
+use std::{collections::btree_map::Values, ops::Bound, sync::Arc};
 
-use super::DbRowsContainer;
+use super::{DbRowsContainer, RowsDirection};
 
 pub struct DbPartition {
     #[cfg(feature = "master-node")]
@@ -19,6 +21,10 @@ pub struct DbPartition {
     #[cfg(feature = "master-node")]
     pub last_write_moment: rust_extensions::date_time::DateTimeAsMicroseconds,
     content_size: usize,
+    sequence: u64,
+    // Rows read with sliding expiration. Their expiration is moved in batch
+    #[cfg(feature = "master-node")]
+    rows_to_extend: Mutex<HashSet<String>>,
 }
 
 impl DbPartition {
@@ -30,11 +36,21 @@ impl DbPartition {
             #[cfg(feature = "master-node")]
             last_write_moment: rust_extensions::date_time::DateTimeAsMicroseconds::now(),
             content_size: 0,
+            sequence: 0,
             #[cfg(feature = "master-node")]
             expires: None,
+            #[cfg(feature = "master-node")]
+            rows_to_extend: Mutex::new(HashSet::new()),
         }
     }
 
+    #[cfg(feature = "master-node")]
+    pub fn new_with_expiration_index(expiration_index: crate::ExpirationIndexKind) -> DbPartition {
+        let mut result = Self::new();
+        result.rows = DbRowsContainer::new_with_expiration_index(expiration_index);
+        result
+    }
+
     #[cfg(feature = "master-node")]
     pub fn get_rows_to_expire(
         &self,
@@ -47,6 +63,24 @@ impl DbPartition {
         self.content_size
     }
 
+    /// Sequence of the last table change which has touched the partition
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub(crate) fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    /// Sequence of the table change which has written the row
+    pub fn get_row_sequence(&self, row_key: &str) -> Option<u64> {
+        self.rows.get_sequence(row_key)
+    }
+
+    pub(crate) fn set_row_sequence(&mut self, row_key: &str, sequence: u64) {
+        self.rows.set_sequence(row_key, sequence);
+    }
+
     pub fn rows_count(&self) -> usize {
         return self.rows.len();
     }
@@ -102,6 +136,20 @@ impl DbPartition {
         result
     }
 
+    #[cfg(feature = "master-node")]
+    pub fn expire_rows(
+        &mut self,
+        now: rust_extensions::date_time::DateTimeAsMicroseconds,
+    ) -> Option<Vec<Arc<DbRow>>> {
+        let result = self.rows.expire_rows(now)?;
+
+        for removed_item in &result {
+            self.content_size -= removed_item.data.len();
+        }
+
+        Some(result)
+    }
+
     pub fn remove_rows_bulk<'s, TRowsIterator: Iterator<Item = &'s String>>(
         &mut self,
         row_keys: TRowsIterator,
@@ -135,6 +183,13 @@ impl DbPartition {
         self.rows.rows_with_expiration_index_len()
     }
 
+    #[cfg(feature = "master-node")]
+    pub fn get_earliest_row_expiration(
+        &self,
+    ) -> Option<rust_extensions::date_time::DateTimeAsMicroseconds> {
+        self.rows.get_earliest_row_expiration()
+    }
+
     pub fn get_row(&self, row_key: &str) -> Option<&Arc<DbRow>> {
         let result = self.rows.get(row_key);
         result
@@ -151,6 +206,17 @@ impl DbPartition {
         }
     }
 
+    pub fn fill_with_projected_json_data<TName: AsRef<str>>(
+        &self,
+        json_array_writer: &mut JsonArrayWriter,
+        fields: &[TName],
+    ) {
+        for db_row in self.rows.get_all() {
+            let projected = crate::db_json_entity::compile_projected_content(&db_row.data, fields);
+            json_array_writer.write_raw_element(projected.as_slice());
+        }
+    }
+
     pub fn get_highest_row_and_below(
         &self,
         row_key: &String,
@@ -159,11 +225,105 @@ impl DbPartition {
         return self.rows.get_highest_row_and_below(row_key, limit);
     }
 
+    pub fn get_rows_range<'s>(
+        &'s self,
+        from: Bound<&str>,
+        to: Bound<&str>,
+        direction: RowsDirection,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        self.rows.get_rows_range(from, to, direction, skip, limit)
+    }
+
+    pub fn get_rows_range_cloned(
+        &self,
+        from: Bound<&str>,
+        to: Bound<&str>,
+        direction: RowsDirection,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<Arc<DbRow>>> {
+        let result = self.rows.get_rows_range(from, to, direction, skip, limit)?;
+        Some(result.into_iter().cloned().collect())
+    }
+
+    pub fn get_rows_by_prefix<'s>(
+        &'s self,
+        row_key_prefix: &str,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        self.rows.get_rows_by_prefix(row_key_prefix, skip, limit)
+    }
+
+    pub fn get_rows_by_prefix_cloned(
+        &self,
+        row_key_prefix: &str,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<Arc<DbRow>>> {
+        let result = self.rows.get_rows_by_prefix(row_key_prefix, skip, limit)?;
+        Some(result.into_iter().cloned().collect())
+    }
+
+    pub fn get_rows_by_filter<'s>(
+        &'s self,
+        filter: &FilterExpression,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        let mut result = LazyVec::new();
+
+        for db_row in self
+            .rows
+            .get_all()
+            .filter(|db_row| filter.matches(db_row))
+            .skip(skip.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+        {
+            result.add(db_row);
+        }
+
+        result.get_result()
+    }
+
+    pub fn get_rows_changed_since(&self, sequence: u64) -> Option<Vec<&Arc<DbRow>>> {
+        if self.sequence <= sequence {
+            return None;
+        }
+
+        let mut result = LazyVec::new();
+
+        for db_row in self.rows.iter_changed_since(sequence) {
+            result.add(db_row);
+        }
+
+        result.get_result()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.rows.len() == 0
     }
 }
 
+/// Sliding expiration is moved only when less than half of the duration is left after the read.
+/// Otherwise every read would rewrite the row JSON and replace the row
+#[cfg(feature = "master-node")]
+pub fn sliding_expiration_is_due(
+    expires: Option<rust_extensions::date_time::DateTimeAsMicroseconds>,
+    read_moment: rust_extensions::date_time::DateTimeAsMicroseconds,
+    duration: Duration,
+) -> bool {
+    match expires {
+        Some(expires) => {
+            expires.unix_microseconds
+                < read_moment.unix_microseconds + (duration.as_micros() / 2) as i64
+        }
+        None => true,
+    }
+}
+
 #[cfg(feature = "master-node")]
 impl DbPartition {
     pub fn update_last_read_moment(&self, now: rust_extensions::date_time::DateTimeAsMicroseconds) {
@@ -177,4 +337,75 @@ impl DbPartition {
     pub fn get_last_read_moment(&self) -> rust_extensions::date_time::DateTimeAsMicroseconds {
         self.last_read_moment.as_date_time()
     }
+
+    /// Reads the row and queues its expiration to be moved by apply_sliding_expiration.
+    /// Row is queued only once its expiration is due, see sliding_expiration_is_due
+    pub fn get_row_and_extend_expiration(
+        &self,
+        row_key: &str,
+        now: rust_extensions::date_time::DateTimeAsMicroseconds,
+        duration: Duration,
+    ) -> Option<&Arc<DbRow>> {
+        let db_row = self.rows.get_and_update_last_read_access(row_key, now)?;
+
+        self.last_read_moment.update(now);
+
+        if sliding_expiration_is_due(db_row.expires, now, duration) {
+            self.rows_to_extend
+                .lock()
+                .unwrap()
+                .insert(row_key.to_string());
+        }
+
+        Some(db_row)
+    }
+
+    /// Moves expiration of the queued rows to the last read moment + duration.
+    /// Returns replaced and new rows
+    pub fn apply_sliding_expiration(
+        &mut self,
+        duration: Duration,
+    ) -> Option<Vec<(Arc<DbRow>, Arc<DbRow>)>> {
+        let rows_to_extend = std::mem::take(self.rows_to_extend.get_mut().unwrap());
+
+        let mut result = LazyVec::new();
+
+        for row_key in rows_to_extend {
+            let db_row = match self.rows.get(&row_key) {
+                Some(db_row) => db_row,
+                None => continue,
+            };
+
+            let last_read_access = db_row.last_read_access.as_date_time();
+
+            if !sliding_expiration_is_due(db_row.expires, last_read_access, duration) {
+                continue;
+            }
+
+            let expires = rust_extensions::date_time::DateTimeAsMicroseconds::new(
+                last_read_access.unix_microseconds + duration.as_micros() as i64,
+            );
+
+            if let Some(updated) = self.update_row_expiration(&row_key, Some(expires)) {
+                result.add(updated);
+            }
+        }
+
+        result.get_result()
+    }
+
+    /// Expires is rewritten in the JSON, so the row is replaced. Returns replaced and new rows
+    pub fn update_row_expiration(
+        &mut self,
+        row_key: &str,
+        expires: Option<rust_extensions::date_time::DateTimeAsMicroseconds>,
+    ) -> Option<(Arc<DbRow>, Arc<DbRow>)> {
+        let removed_db_row = self.rows.update_expiration_time(row_key, expires)?;
+        let new_db_row = self.rows.get(row_key).unwrap().clone();
+
+        self.content_size += new_db_row.data.len();
+        self.content_size -= removed_db_row.data.len();
+
+        Some((removed_db_row, new_db_row))
+    }
 }
@@ -1,18 +1,36 @@
 #[cfg(feature = "master-node")]
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 use rust_extensions::lazy::LazyVec;
+#[cfg(feature = "master-node")]
+use std::{collections::HashSet, sync::Mutex};
 use std::{
     collections::{btree_map::Values, BTreeMap},
+    ops::Bound,
     sync::Arc,
 };
 
 use crate::db::DbRow;
 
+#[cfg(feature = "master-node")]
+use super::lru_index::{LruIndex, LruRowsIterator};
+use super::{row_sequence_index::RowSequenceIndex, RowsDirection};
+
 pub struct DbRowsContainer {
     data: BTreeMap<String, Arc<DbRow>>,
 
     #[cfg(feature = "master-node")]
-    rows_with_expiration_index: crate::ExpirationIndex<Arc<DbRow>>,
+    rows_with_expiration_index: crate::AnyExpirationIndex<Arc<DbRow>>,
+    // Bucketed index scans the whole bucket to find it, so it's kept here for every write to check
+    #[cfg(feature = "master-node")]
+    earliest_row_expiration: Option<DateTimeAsMicroseconds>,
+
+    #[cfg(feature = "master-node")]
+    lru_index: LruIndex,
+    // Rows read since they were indexed. They are moved in LRU index by the next write or GC
+    #[cfg(feature = "master-node")]
+    read_since_indexing: Mutex<HashSet<String>>,
+
+    sequences: RowSequenceIndex,
 }
 
 impl DbRowsContainer {
@@ -20,7 +38,26 @@ impl DbRowsContainer {
         Self {
             data: BTreeMap::new(),
             #[cfg(feature = "master-node")]
-            rows_with_expiration_index: crate::ExpirationIndex::new(),
+            rows_with_expiration_index: crate::AnyExpirationIndex::new(Default::default()),
+            #[cfg(feature = "master-node")]
+            earliest_row_expiration: None,
+            #[cfg(feature = "master-node")]
+            lru_index: LruIndex::new(),
+            #[cfg(feature = "master-node")]
+            read_since_indexing: Mutex::new(HashSet::new()),
+            sequences: RowSequenceIndex::new(),
+        }
+    }
+
+    #[cfg(feature = "master-node")]
+    pub fn new_with_expiration_index(expiration_index: crate::ExpirationIndexKind) -> Self {
+        Self {
+            data: BTreeMap::new(),
+            rows_with_expiration_index: crate::AnyExpirationIndex::new(expiration_index),
+            earliest_row_expiration: None,
+            lru_index: LruIndex::new(),
+            read_since_indexing: Mutex::new(HashSet::new()),
+            sequences: RowSequenceIndex::new(),
         }
     }
 
@@ -32,57 +69,154 @@ impl DbRowsContainer {
     pub fn rows_with_expiration_index_len(&self) -> usize {
         self.rows_with_expiration_index.len()
     }
+
+    #[cfg(feature = "master-node")]
+    pub fn get_earliest_row_expiration(&self) -> Option<DateTimeAsMicroseconds> {
+        self.earliest_row_expiration
+    }
+
+    // Index is searched only if the earliest row has gone and nothing earlier has come
+    #[cfg(feature = "master-node")]
+    fn update_earliest_row_expiration(
+        &mut self,
+        removed: Option<DateTimeAsMicroseconds>,
+        added: Option<DateTimeAsMicroseconds>,
+    ) {
+        let earliest = self
+            .earliest_row_expiration
+            .map(|itm| itm.unix_microseconds);
+
+        if let Some(added) = added {
+            let is_earliest = match earliest {
+                Some(earliest) => added.unix_microseconds <= earliest,
+                None => true,
+            };
+
+            if is_earliest {
+                self.earliest_row_expiration = Some(added);
+                return;
+            }
+        }
+
+        if removed.is_some() && removed.map(|itm| itm.unix_microseconds) == earliest {
+            self.earliest_row_expiration = self
+                .rows_with_expiration_index
+                .get_earliest_expiration_moment();
+        }
+    }
     #[cfg(feature = "master-node")]
     pub fn get_rows_to_expire(&self, now: DateTimeAsMicroseconds) -> Option<Vec<&Arc<DbRow>>> {
         self.rows_with_expiration_index.get_items_to_expire(now)
     }
 
+    /// Gives the least recently read rows above the max amount
     #[cfg(feature = "master-node")]
     pub fn get_rows_to_gc_by_max_amount(&self, max_rows_amount: usize) -> Option<Vec<&Arc<DbRow>>> {
         if self.data.len() <= max_rows_amount {
             return None;
         }
 
-        let mut by_last_read_access = BTreeMap::new();
+        let amount_to_gc = self.data.len() - max_rows_amount;
 
-        for db_row in self.data.values() {
-            let last_read_access = db_row.last_read_access.get_unix_microseconds();
-            by_last_read_access.insert(last_read_access, db_row.clone());
-        }
+        Some(self.iter_least_recently_read().take(amount_to_gc).collect())
+    }
 
-        let max_records_amount = self.data.len() - max_rows_amount;
+    /// Rows ordered from the least recently read one
+    #[cfg(feature = "master-node")]
+    pub fn iter_least_recently_read(&self) -> impl Iterator<Item = &Arc<DbRow>> {
+        LruRowsIterator::new(&self.lru_index, &self.data)
+    }
 
-        let mut result = Vec::with_capacity(max_records_amount);
+    /// Reads the row and queues it to be moved in LRU index
+    #[cfg(feature = "master-node")]
+    pub fn get_and_update_last_read_access(
+        &self,
+        row_key: &str,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<&Arc<DbRow>> {
+        let db_row = self.data.get(row_key)?;
 
-        for db_row in self.data.values().take(max_records_amount) {
-            result.push(db_row);
-        }
+        db_row.update_last_read_access(now);
+
+        self.read_since_indexing
+            .lock()
+            .unwrap()
+            .insert(row_key.to_string());
+
+        Some(db_row)
+    }
 
-        Some(result)
+    /// Moves the rows read since they were indexed to their current place in LRU index,
+    /// so they are not revisited by every GC pass. Called by every write and by GC
+    #[cfg(feature = "master-node")]
+    pub fn update_lru_index(&mut self) {
+        let read_since_indexing = std::mem::take(self.read_since_indexing.get_mut().unwrap());
+
+        for row_key in read_since_indexing {
+            if let Some(db_row) = self.data.get(&row_key) {
+                self.lru_index
+                    .update(&row_key, db_row.last_read_access.get_unix_microseconds());
+            }
+        }
     }
 
     pub fn insert(&mut self, db_row: Arc<DbRow>) -> Option<Arc<DbRow>> {
         #[cfg(feature = "master-node")]
-        self.rows_with_expiration_index.add(db_row.expires, &db_row);
+        self.update_lru_index();
 
-        let result = self.data.insert(db_row.row_key.to_string(), db_row);
+        #[cfg(feature = "master-node")]
+        self.lru_index.update(
+            &db_row.row_key,
+            db_row.last_read_access.get_unix_microseconds(),
+        );
+
+        let result = self.data.insert(db_row.row_key.to_string(), db_row.clone());
+
+        // Sequence of the replaced row does not belong to the new one
+        if result.is_some() {
+            self.sequences.remove(&db_row.row_key);
+        }
 
         #[cfg(feature = "master-node")]
         if let Some(removed_db_row) = &result {
             self.rows_with_expiration_index
-                .remove(removed_db_row.expires, &removed_db_row);
+                .remove(removed_db_row.expires, removed_db_row);
         }
 
+        #[cfg(feature = "master-node")]
+        self.rows_with_expiration_index.add(db_row.expires, &db_row);
+
+        #[cfg(feature = "master-node")]
+        self.update_earliest_row_expiration(
+            result
+                .as_ref()
+                .and_then(|removed_db_row| removed_db_row.expires),
+            db_row.expires,
+        );
+
         result
     }
 
     pub fn remove(&mut self, row_key: &str) -> Option<Arc<DbRow>> {
+        #[cfg(feature = "master-node")]
+        self.update_lru_index();
+
         let result = self.data.remove(row_key);
 
+        if result.is_some() {
+            self.sequences.remove(row_key);
+        }
+
+        #[cfg(feature = "master-node")]
+        if result.is_some() {
+            self.lru_index.remove(row_key);
+        }
+
         #[cfg(feature = "master-node")]
         if let Some(removed_db_row) = &result {
             self.rows_with_expiration_index
                 .remove(removed_db_row.expires, &removed_db_row);
+            self.update_earliest_row_expiration(removed_db_row.expires, None);
         }
 
         result
@@ -92,6 +226,24 @@ impl DbRowsContainer {
         self.data.get(row_key)
     }
 
+    /// Sequence of the table change which has written the row. None - row was not written by the table
+    pub fn get_sequence(&self, row_key: &str) -> Option<u64> {
+        self.sequences.get(row_key)
+    }
+
+    pub(crate) fn set_sequence(&mut self, row_key: &str, sequence: u64) {
+        if self.data.contains_key(row_key) {
+            self.sequences.update(row_key, sequence);
+        }
+    }
+
+    /// Rows written after the change with the given sequence, ordered by sequence
+    pub fn iter_changed_since(&self, sequence: u64) -> impl Iterator<Item = &Arc<DbRow>> {
+        self.sequences
+            .iter_since(sequence)
+            .filter_map(|row_key| self.data.get(row_key))
+    }
+
     pub fn has_db_row(&self, row_key: &str) -> bool {
         return self.data.contains_key(row_key);
     }
@@ -122,6 +274,108 @@ impl DbRowsContainer {
         result.get_result()
     }
 
+    pub fn iter_rows_range<'s>(
+        &'s self,
+        from: Bound<&str>,
+        to: Bound<&str>,
+    ) -> impl DoubleEndedIterator<Item = &'s Arc<DbRow>> {
+        let range = if range_is_valid(from, to) {
+            self.data.range::<str, _>((from, to))
+        } else {
+            self.data
+                .range::<str, _>((Bound::Included(""), Bound::Excluded("")))
+        };
+
+        range.map(|(_, db_row)| db_row)
+    }
+
+    pub fn get_rows_range<'s>(
+        &'s self,
+        from: Bound<&str>,
+        to: Bound<&str>,
+        direction: RowsDirection,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        let range = self.iter_rows_range(from, to);
+
+        let skip = skip.unwrap_or(0);
+        let limit = limit.unwrap_or(usize::MAX);
+
+        let mut result = LazyVec::new();
+
+        match direction {
+            RowsDirection::Ascending => {
+                for db_row in range.skip(skip).take(limit) {
+                    result.add(db_row);
+                }
+            }
+            RowsDirection::Descending => {
+                for db_row in range.rev().skip(skip).take(limit) {
+                    result.add(db_row);
+                }
+            }
+        }
+
+        result.get_result()
+    }
+
+    pub fn iter_rows_by_prefix<'s, 'p>(
+        &'s self,
+        row_key_prefix: &'p str,
+    ) -> impl Iterator<Item = &'s Arc<DbRow>> + 'p
+    where
+        's: 'p,
+    {
+        self.data
+            .range::<str, _>((Bound::Included(row_key_prefix), Bound::Unbounded))
+            .take_while(move |(row_key, _)| row_key.starts_with(row_key_prefix))
+            .map(|(_, db_row)| db_row)
+    }
+
+    pub fn get_rows_by_prefix<'s>(
+        &'s self,
+        row_key_prefix: &str,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        let mut result = LazyVec::new();
+
+        for db_row in self
+            .iter_rows_by_prefix(row_key_prefix)
+            .skip(skip.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+        {
+            result.add(db_row);
+        }
+
+        result.get_result()
+    }
+
+    /// Removes the expired rows in one pass through the expiration index
+    #[cfg(feature = "master-node")]
+    pub fn expire_rows(&mut self, now: DateTimeAsMicroseconds) -> Option<Vec<Arc<DbRow>>> {
+        self.update_lru_index();
+
+        let expired_rows = self.rows_with_expiration_index.drain_items_to_expire(now)?;
+
+        let mut result = LazyVec::new();
+
+        for expired_row in expired_rows {
+            if let Some(removed_row) = self.data.remove(&expired_row.row_key) {
+                self.lru_index.remove(&removed_row.row_key);
+                self.sequences.remove(&removed_row.row_key);
+                result.add(removed_row);
+            }
+        }
+
+        self.earliest_row_expiration = self
+            .rows_with_expiration_index
+            .get_earliest_expiration_moment();
+
+        result.get_result()
+    }
+
     #[cfg(feature = "master-node")]
     pub fn update_expiration_time(
         &mut self,
@@ -142,6 +396,8 @@ impl DbRowsContainer {
             }
         }
 
+        self.update_lru_index();
+
         let removed_db_row = self.data.remove(row_key)?;
 
         let new_db_row = removed_db_row.create_with_new_expiration_time(expiration_time);
@@ -154,21 +410,38 @@ impl DbRowsContainer {
             &new_db_row,
         );
 
+        self.update_earliest_row_expiration(removed_db_row.expires, new_db_row.expires);
+
         self.data.insert(row_key.to_string(), new_db_row);
 
         Some(removed_db_row)
     }
 }
 
+// BTreeMap::range panics if the start of the range is after the end of the range.
+// Invalid ranges are served as an empty range instead
+fn range_is_valid(from: Bound<&str>, to: Bound<&str>) -> bool {
+    match (from, to) {
+        (Bound::Included(from), Bound::Included(to)) => from <= to,
+        (Bound::Included(from), Bound::Excluded(to))
+        | (Bound::Excluded(from), Bound::Included(to))
+        | (Bound::Excluded(from), Bound::Excluded(to)) => from < to,
+        _ => true,
+    }
+}
+
 #[cfg(feature = "master-node")]
 #[cfg(test)]
 mod tests {
 
     use rust_extensions::date_time::DateTimeAsMicroseconds;
 
-    use crate::db_json_entity::{DbJsonEntity, JsonTimeStamp};
+    use crate::{
+        db_json_entity::{DbJsonEntity, JsonTimeStamp},
+        test_utils::{create_db_row, create_db_row_at},
+    };
 
-    use super::*;
+    use super::{range_tests::to_row_keys, *};
 
     #[test]
     fn test_that_index_appears() {
@@ -187,6 +460,30 @@ mod tests {
         assert_eq!(1, db_rows.rows_with_expiration_index.len())
     }
 
+    #[test]
+    fn test_that_index_stays_when_row_is_replaced_with_same_expiration() {
+        let test_json = r#"{
+            "PartitionKey": "test",
+            "RowKey": "test",
+            "Expires": "2019-01-01T00:00:00",
+        }"#;
+
+        let db_json_entity = DbJsonEntity::parse(test_json.as_bytes()).unwrap();
+
+        let mut db_rows = DbRowsContainer::new();
+        let time_stamp = JsonTimeStamp::now();
+        db_rows.insert(Arc::new(db_json_entity.new_db_row(&time_stamp)));
+        db_rows.insert(Arc::new(db_json_entity.new_db_row(&time_stamp)));
+
+        assert_eq!(1, db_rows.rows_with_expiration_index.len());
+        db_rows.rows_with_expiration_index.debug_validate();
+
+        db_rows.remove("test");
+
+        assert_eq!(0, db_rows.rows_with_expiration_index.len());
+        db_rows.rows_with_expiration_index.debug_validate();
+    }
+
     #[test]
     fn test_that_index_does_not_appear_since_we_do_not_have_expiration() {
         let test_json = r#"{
@@ -441,4 +738,234 @@ mod tests {
 
         assert_eq!("test1", &db_rows_to_gc.get(0).unwrap().row_key);
     }
+
+    fn insert_row_read_at(db_rows: &mut DbRowsContainer, row_key: &str, last_read_access: i64) {
+        let time_stamp =
+            JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(last_read_access));
+        db_rows.insert(create_db_row_at("test", row_key, &time_stamp));
+    }
+
+    #[test]
+    fn test_earliest_row_expiration_follows_changes() {
+        let mut db_rows =
+            DbRowsContainer::new_with_expiration_index(crate::ExpirationIndexKind::Bucketed {
+                resolution: std::time::Duration::from_secs(10),
+            });
+
+        let earliest = |db_rows: &DbRowsContainer| {
+            db_rows
+                .get_earliest_row_expiration()
+                .map(|itm| itm.unix_microseconds)
+        };
+
+        for row_key in ["a", "b", "c"] {
+            db_rows.insert(create_db_row("test", row_key));
+        }
+
+        assert_eq!(None, earliest(&db_rows));
+
+        db_rows.update_expiration_time("a", Some(DateTimeAsMicroseconds::new(3_000_000)));
+        db_rows.update_expiration_time("b", Some(DateTimeAsMicroseconds::new(1_000_000)));
+        db_rows.update_expiration_time("c", Some(DateTimeAsMicroseconds::new(2_000_000)));
+        assert_eq!(Some(1_000_000), earliest(&db_rows));
+
+        db_rows.remove("b");
+        assert_eq!(Some(2_000_000), earliest(&db_rows));
+
+        db_rows.update_expiration_time("c", Some(DateTimeAsMicroseconds::new(4_000_000)));
+        assert_eq!(Some(3_000_000), earliest(&db_rows));
+
+        db_rows.expire_rows(DateTimeAsMicroseconds::new(3_000_000));
+        assert_eq!(Some(4_000_000), earliest(&db_rows));
+
+        db_rows.update_expiration_time("c", None);
+        assert_eq!(None, earliest(&db_rows));
+    }
+
+    #[test]
+    fn test_gc_by_max_amount_takes_least_recently_read_rows() {
+        let mut db_rows = DbRowsContainer::new();
+
+        // Row key order is the opposite of the access order
+        insert_row_read_at(&mut db_rows, "a", 3_000_000);
+        insert_row_read_at(&mut db_rows, "b", 2_000_000);
+        insert_row_read_at(&mut db_rows, "c", 1_000_000);
+        insert_row_read_at(&mut db_rows, "d", 4_000_000);
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(2);
+        assert_eq!(vec!["c", "b"], to_row_keys(db_rows_to_gc));
+
+        // Reading the row saves it from GC
+        db_rows
+            .get_and_update_last_read_access("c", DateTimeAsMicroseconds::new(5_000_000))
+            .unwrap();
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(2);
+        assert_eq!(vec!["b", "a"], to_row_keys(db_rows_to_gc));
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(0);
+        assert_eq!(vec!["b", "a", "d", "c"], to_row_keys(db_rows_to_gc));
+
+        // The next write moves the read row in the index
+        insert_row_read_at(&mut db_rows, "e", 6_000_000);
+        assert_eq!(
+            vec![
+                (2_000_000, "b"),
+                (3_000_000, "a"),
+                (4_000_000, "d"),
+                (5_000_000, "c"),
+                (6_000_000, "e")
+            ],
+            db_rows
+                .lru_index
+                .iter()
+                .map(|(access, row_key)| (*access, row_key.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(2);
+        assert_eq!(vec!["b", "a", "d"], to_row_keys(db_rows_to_gc));
+    }
+
+    #[test]
+    fn test_gc_by_max_amount_with_same_access_moment() {
+        let mut db_rows = DbRowsContainer::new();
+
+        insert_row_read_at(&mut db_rows, "a", 2_000_000);
+        insert_row_read_at(&mut db_rows, "b", 1_000_000);
+        insert_row_read_at(&mut db_rows, "c", 1_000_000);
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(1);
+        assert_eq!(vec!["b", "c"], to_row_keys(db_rows_to_gc));
+
+        db_rows.remove("b");
+
+        let db_rows_to_gc = db_rows.get_rows_to_gc_by_max_amount(1);
+        assert_eq!(vec!["c"], to_row_keys(db_rows_to_gc));
+    }
+}
+
+#[cfg(test)]
+mod range_tests {
+    use crate::test_utils::create_db_row;
+
+    use super::*;
+
+    fn create_rows_container(row_keys: &[&str]) -> DbRowsContainer {
+        let mut db_rows = DbRowsContainer::new();
+
+        for row_key in row_keys {
+            db_rows.insert(create_db_row("test", row_key));
+        }
+
+        db_rows
+    }
+
+    pub(super) fn to_row_keys(db_rows: Option<Vec<&Arc<DbRow>>>) -> Vec<String> {
+        match db_rows {
+            Some(db_rows) => db_rows.iter().map(|itm| itm.row_key.to_string()).collect(),
+            None => vec![],
+        }
+    }
+
+    #[test]
+    fn test_rows_range_with_included_and_excluded_bounds() {
+        let db_rows = create_rows_container(&["1", "2", "3", "4", "5"]);
+
+        let result = db_rows.get_rows_range(
+            Bound::Included("2"),
+            Bound::Excluded("4"),
+            RowsDirection::Ascending,
+            None,
+            None,
+        );
+        assert_eq!(vec!["2", "3"], to_row_keys(result));
+
+        let result = db_rows.get_rows_range(
+            Bound::Excluded("2"),
+            Bound::Included("4"),
+            RowsDirection::Ascending,
+            None,
+            None,
+        );
+        assert_eq!(vec!["3", "4"], to_row_keys(result));
+
+        let result = db_rows.get_rows_range(
+            Bound::Excluded("3"),
+            Bound::Unbounded,
+            RowsDirection::Ascending,
+            None,
+            None,
+        );
+        assert_eq!(vec!["4", "5"], to_row_keys(result));
+    }
+
+    #[test]
+    fn test_rows_range_descending_with_skip_and_limit() {
+        let db_rows = create_rows_container(&["1", "2", "3", "4", "5"]);
+
+        let result = db_rows.get_rows_range(
+            Bound::Unbounded,
+            Bound::Unbounded,
+            RowsDirection::Descending,
+            None,
+            Some(2),
+        );
+        assert_eq!(vec!["5", "4"], to_row_keys(result));
+
+        let result = db_rows.get_rows_range(
+            Bound::Unbounded,
+            Bound::Included("4"),
+            RowsDirection::Descending,
+            Some(1),
+            Some(2),
+        );
+        assert_eq!(vec!["3", "2"], to_row_keys(result));
+    }
+
+    #[test]
+    fn test_rows_range_with_inverted_bounds() {
+        let db_rows = create_rows_container(&["1", "2", "3"]);
+
+        let result = db_rows.get_rows_range(
+            Bound::Included("3"),
+            Bound::Included("1"),
+            RowsDirection::Ascending,
+            None,
+            None,
+        );
+        assert!(result.is_none());
+
+        let result = db_rows.get_rows_range(
+            Bound::Included("2"),
+            Bound::Excluded("2"),
+            RowsDirection::Ascending,
+            None,
+            None,
+        );
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_rows_by_prefix() {
+        let db_rows = create_rows_container(&[
+            "user:1:order:1",
+            "user:1:order:2",
+            "user:1:profile",
+            "user:10:order:1",
+            "user:2:order:1",
+        ]);
+
+        let result = db_rows.get_rows_by_prefix("user:1:", None, None);
+        assert_eq!(
+            vec!["user:1:order:1", "user:1:order:2", "user:1:profile"],
+            to_row_keys(result)
+        );
+
+        let result = db_rows.get_rows_by_prefix("user:1:order:", Some(1), Some(5));
+        assert_eq!(vec!["user:1:order:2"], to_row_keys(result));
+
+        let result = db_rows.get_rows_by_prefix("user:3", None, None);
+        assert!(result.is_none());
+    }
 }
@@ -0,0 +1,147 @@
+use std::{
+    cmp::Reverse,
+    collections::{btree_set, BTreeMap, BTreeSet, BinaryHeap, HashMap},
+    iter::Peekable,
+    sync::Arc,
+};
+
+use crate::db::DbRow;
+
+// Rows are ordered by the last read access known at the moment of indexing.
+// Reads update DbRow::last_read_access and get to the index with the next write or GC,
+// so the indexed value is the lower bound of the real one.
+pub struct LruIndex {
+    by_access: BTreeSet<(i64, String)>,
+    positions: HashMap<String, i64>,
+}
+
+impl LruIndex {
+    pub fn new() -> Self {
+        Self {
+            by_access: BTreeSet::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, row_key: &str, last_read_access: i64) {
+        if let Some(indexed) = self.positions.get(row_key) {
+            if *indexed == last_read_access {
+                return;
+            }
+
+            self.by_access.remove(&(*indexed, row_key.to_string()));
+        }
+
+        self.by_access
+            .insert((last_read_access, row_key.to_string()));
+        self.positions.insert(row_key.to_string(), last_read_access);
+    }
+
+    pub fn remove(&mut self, row_key: &str) {
+        if let Some(indexed) = self.positions.remove(row_key) {
+            self.by_access.remove(&(indexed, row_key.to_string()));
+        }
+    }
+
+    pub fn iter(&self) -> btree_set::Iter<'_, (i64, String)> {
+        self.by_access.iter()
+    }
+}
+
+pub struct LruRowsIterator<'s> {
+    index: Peekable<btree_set::Iter<'s, (i64, String)>>,
+    rows: &'s BTreeMap<String, Arc<DbRow>>,
+    // Rows read after indexing wait here ordered by their real last read access.
+    // Such a row can be given away as soon as the index reaches its real value,
+    // since the index is ordered by the lower bound of the last read access
+    read_after_indexing: BinaryHeap<Reverse<(i64, LruRow<'s>)>>,
+}
+
+impl<'s> LruRowsIterator<'s> {
+    pub fn new(index: &'s LruIndex, rows: &'s BTreeMap<String, Arc<DbRow>>) -> Self {
+        Self {
+            index: index.iter().peekable(),
+            rows,
+            read_after_indexing: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<'s> Iterator for LruRowsIterator<'s> {
+    type Item = &'s Arc<DbRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((indexed_access, _)) = self.index.peek() else {
+                let Reverse((_, LruRow(db_row))) = self.read_after_indexing.pop()?;
+                return Some(db_row);
+            };
+
+            if let Some(Reverse((last_read_access, _))) = self.read_after_indexing.peek() {
+                if last_read_access <= indexed_access {
+                    let Reverse((_, LruRow(db_row))) = self.read_after_indexing.pop().unwrap();
+                    return Some(db_row);
+                }
+            }
+
+            let (indexed_access, row_key) = self.index.next().unwrap();
+
+            let db_row = self.rows.get(row_key).unwrap();
+            let last_read_access = db_row.last_read_access.get_unix_microseconds();
+
+            if last_read_access > *indexed_access {
+                self.read_after_indexing
+                    .push(Reverse((last_read_access, LruRow(db_row))));
+                continue;
+            }
+
+            return Some(db_row);
+        }
+    }
+}
+
+// Row keys are unique within the partition, so they are enough to order the rows with the same access moment
+struct LruRow<'s>(&'s Arc<DbRow>);
+
+impl PartialEq for LruRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.row_key == other.0.row_key
+    }
+}
+
+impl Eq for LruRow<'_> {}
+
+impl PartialOrd for LruRow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LruRow<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.row_key.cmp(&other.0.row_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruIndex;
+
+    #[test]
+    fn test_same_access_moment_does_not_collide() {
+        let mut index = LruIndex::new();
+
+        index.update("rk1", 10);
+        index.update("rk2", 10);
+        index.update("rk3", 5);
+
+        let result: Vec<_> = index.iter().map(|(a, k)| (*a, k.as_str())).collect();
+        assert_eq!(vec![(5, "rk3"), (10, "rk1"), (10, "rk2")], result);
+
+        index.update("rk3", 20);
+        index.remove("rk1");
+
+        let result: Vec<_> = index.iter().map(|(a, k)| (*a, k.as_str())).collect();
+        assert_eq!(vec![(10, "rk2"), (20, "rk3")], result);
+    }
+}
@@ -1,5 +1,10 @@
 mod db_partition;
 
 mod db_rows_container;
+#[cfg(feature = "master-node")]
+mod lru_index;
+mod row_sequence_index;
+mod rows_direction;
 pub use db_partition::*;
 pub use db_rows_container::*;
+pub use rows_direction::*;
@@ -0,0 +1,74 @@
+use std::collections::{BTreeSet, HashMap};
+
+// Sequences of the table changes which have written the rows. Rows are shared between tables
+// through Arc<DbRow>, so the sequence belongs to the partition and not to the row itself
+pub struct RowSequenceIndex {
+    by_sequence: BTreeSet<(u64, String)>,
+    sequences: HashMap<String, u64>,
+}
+
+impl RowSequenceIndex {
+    pub fn new() -> Self {
+        Self {
+            by_sequence: BTreeSet::new(),
+            sequences: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, row_key: &str) -> Option<u64> {
+        self.sequences.get(row_key).copied()
+    }
+
+    pub fn update(&mut self, row_key: &str, sequence: u64) {
+        if let Some(indexed) = self.sequences.get(row_key) {
+            if *indexed == sequence {
+                return;
+            }
+
+            self.by_sequence.remove(&(*indexed, row_key.to_string()));
+        }
+
+        self.by_sequence.insert((sequence, row_key.to_string()));
+        self.sequences.insert(row_key.to_string(), sequence);
+    }
+
+    pub fn remove(&mut self, row_key: &str) {
+        if let Some(indexed) = self.sequences.remove(row_key) {
+            self.by_sequence.remove(&(indexed, row_key.to_string()));
+        }
+    }
+
+    /// Row keys written after the change with the given sequence, ordered by sequence
+    pub fn iter_since(&self, sequence: u64) -> impl Iterator<Item = &str> {
+        self.by_sequence
+            .range((sequence.saturating_add(1), String::new())..)
+            .map(|(_, row_key)| row_key.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RowSequenceIndex;
+
+    #[test]
+    fn test_iter_since() {
+        let mut index = RowSequenceIndex::new();
+
+        index.update("rk1", 1);
+        index.update("rk2", 2);
+        index.update("rk3", 2);
+        index.update("rk1", 3);
+
+        let result: Vec<&str> = index.iter_since(1).collect();
+        assert_eq!(vec!["rk2", "rk3", "rk1"], result);
+
+        index.remove("rk2");
+
+        let result: Vec<&str> = index.iter_since(2).collect();
+        assert_eq!(vec!["rk1"], result);
+
+        assert_eq!(Some(2), index.get("rk3"));
+        assert_eq!(None, index.get("rk2"));
+        assert_eq!(0, index.iter_since(u64::MAX).count());
+    }
+}
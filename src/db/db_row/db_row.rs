@@ -44,6 +44,28 @@ impl DbRow {
         }
     }
 
+    /// Restores the row from the already parsed data. Used by snapshot reader
+    #[cfg(feature = "master-node")]
+    pub(crate) fn restore(
+        partition_key: String,
+        row_key: String,
+        data: Vec<u8>,
+        expires: Option<DateTimeAsMicroseconds>,
+        expires_json_position: Option<crate::db_json_entity::JsonKeyValuePosition>,
+        time_stamp: String,
+        last_read_access: DateTimeAsMicroseconds,
+    ) -> Self {
+        Self {
+            partition_key,
+            row_key,
+            data,
+            expires,
+            expires_json_position,
+            time_stamp,
+            last_read_access: AtomicDateTimeAsMicroseconds::new(last_read_access.unix_microseconds),
+        }
+    }
+
     #[cfg(feature = "master-node")]
     pub fn update_last_read_access(&self, now: rust_extensions::date_time::DateTimeAsMicroseconds) {
         self.last_read_access.update(now);
@@ -92,3 +114,9 @@ impl crate::ExpirationItemsAreSame<Arc<DbRow>> for Arc<DbRow> {
         self.row_key == other_one.row_key
     }
 }
+
+impl crate::ExpirationItemKey for Arc<DbRow> {
+    fn get_expiration_key(&self) -> &str {
+        self.row_key.as_str()
+    }
+}
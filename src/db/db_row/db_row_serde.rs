@@ -0,0 +1,85 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::db_json_entity::{DbEntityParseFail, DbJsonEntity, JsonTimeStamp};
+
+use super::DbRow;
+
+impl DbRow {
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(self.data.as_slice())
+    }
+
+    /// Content goes through the same parse and TimeStamp injection as JSON does,
+    /// so stored data is the same as we would get from the JSON of the entity
+    pub fn from_serializable<T: Serialize>(
+        entity: &T,
+        time_stamp: &JsonTimeStamp,
+    ) -> Result<Self, DbEntityParseFail> {
+        let raw = serde_json::to_vec(entity)?;
+        let db_json_entity = DbJsonEntity::parse(raw.as_slice())?;
+        Ok(db_json_entity.new_db_row(time_stamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        db::DbRow,
+        db_json_entity::{DbJsonEntity, JsonTimeStamp},
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct TestEntity {
+        #[serde(rename = "PartitionKey")]
+        partition_key: String,
+        #[serde(rename = "RowKey")]
+        row_key: String,
+        #[serde(rename = "Amount")]
+        amount: i64,
+    }
+
+    #[test]
+    fn test_from_serializable_and_back() {
+        let entity = TestEntity {
+            partition_key: "pk".to_string(),
+            row_key: "rk".to_string(),
+            amount: 15,
+        };
+
+        let time_stamp = JsonTimeStamp::now();
+
+        let db_row = DbRow::from_serializable(&entity, &time_stamp).unwrap();
+
+        let json = r#"{"PartitionKey":"pk","RowKey":"rk","Amount":15}"#;
+        let expected_db_row = DbJsonEntity::parse(json.as_bytes())
+            .unwrap()
+            .new_db_row(&time_stamp);
+
+        assert_eq!("pk", db_row.partition_key);
+        assert_eq!("rk", db_row.row_key);
+        assert_eq!(expected_db_row.data, db_row.data);
+
+        let result: TestEntity = db_row.deserialize().unwrap();
+
+        assert_eq!(entity, result);
+    }
+
+    #[test]
+    fn test_from_serializable_without_row_key() {
+        #[derive(Serialize)]
+        struct NoRowKey {
+            #[serde(rename = "PartitionKey")]
+            partition_key: String,
+        }
+
+        let entity = NoRowKey {
+            partition_key: "pk".to_string(),
+        };
+
+        let result = DbRow::from_serializable(&entity, &JsonTimeStamp::now());
+
+        assert!(result.is_err());
+    }
+}
@@ -1,3 +1,5 @@
 mod db_row;
+#[cfg(feature = "serde")]
+mod db_row_serde;
 
 pub use db_row::*;
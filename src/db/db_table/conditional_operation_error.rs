@@ -0,0 +1,22 @@
+use crate::validations::SchemaValidationError;
+
+#[derive(Debug)]
+pub enum ConditionalOperationError {
+    RowNotFound {
+        partition_key: String,
+        row_key: String,
+    },
+    TimeStampMismatch {
+        partition_key: String,
+        row_key: String,
+        expected: String,
+        actual: String,
+    },
+    SchemaValidationError(SchemaValidationError),
+}
+
+impl From<SchemaValidationError> for ConditionalOperationError {
+    fn from(src: SchemaValidationError) -> Self {
+        Self::SchemaValidationError(src)
+    }
+}
@@ -0,0 +1,66 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuationToken {
+    pub partition_key: String,
+    pub row_key: String,
+}
+
+impl ContinuationToken {
+    pub fn new(partition_key: &str, row_key: &str) -> Self {
+        Self {
+            partition_key: partition_key.to_string(),
+            row_key: row_key.to_string(),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(
+            "{}:{}{}",
+            self.partition_key.len(),
+            self.partition_key,
+            self.row_key
+        )
+    }
+
+    pub fn deserialize(src: &str) -> Option<Self> {
+        let separator = src.find(':')?;
+
+        let partition_key_len: usize = src[..separator].parse().ok()?;
+
+        let keys = &src[separator + 1..];
+
+        if partition_key_len > keys.len() || !keys.is_char_boundary(partition_key_len) {
+            return None;
+        }
+
+        let (partition_key, row_key) = keys.split_at(partition_key_len);
+
+        Some(Self::new(partition_key, row_key))
+    }
+}
+
+#[derive(Debug)]
+pub enum ContinuationTokenError {
+    /// Token was issued while reading another partition
+    PartitionKeyMismatch { expected: String, actual: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContinuationToken;
+
+    #[test]
+    fn test_serialize_and_deserialize() {
+        let token = ContinuationToken::new("pk:1", "rk:2:3");
+
+        let serialized = token.serialize();
+
+        assert_eq!(Some(token), ContinuationToken::deserialize(&serialized));
+    }
+
+    #[test]
+    fn test_deserialize_invalid_token() {
+        assert_eq!(None, ContinuationToken::deserialize("abc"));
+        assert_eq!(None, ContinuationToken::deserialize("10:pk"));
+        assert_eq!(None, ContinuationToken::deserialize("x:pk"));
+    }
+}
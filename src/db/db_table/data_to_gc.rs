@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 
+use super::DbTombstone;
+
 pub struct DataToGceInner {
     pub partitions: HashMap<String, ()>,
     pub db_rows: HashMap<String, Vec<String>>,
+    pub tombstones: Vec<DbTombstone>,
 }
 
 pub struct DataToGc {
@@ -19,6 +22,7 @@ impl DataToGc {
             self.inner = Some(DataToGceInner {
                 partitions: HashMap::new(),
                 db_rows: HashMap::new(),
+                tombstones: Vec::new(),
             });
         }
         self.inner.as_mut().unwrap()
@@ -55,6 +59,10 @@ impl DataToGc {
         inner.db_rows.get_mut(partition_key).unwrap().extend(rows);
     }
 
+    pub fn add_tombstone_to_gc(&mut self, tombstone: &DbTombstone) {
+        self.get_inner().tombstones.push(tombstone.clone());
+    }
+
     pub fn has_partition_to_gc(&self, partition_key: &str) -> bool {
         if let Some(inner) = self.inner.as_ref() {
             inner.partitions.contains_key(partition_key)
@@ -77,6 +85,11 @@ impl DataToGc {
         Some(&inner.db_rows)
     }
 
+    pub fn get_tombstones_to_gc(&self) -> Option<&Vec<DbTombstone>> {
+        let inner = self.inner.as_ref()?;
+        Some(&inner.tombstones)
+    }
+
     pub fn get_data_to_gc(self) -> Option<DataToGceInner> {
         self.inner
     }
@@ -1,21 +1,55 @@
 #[cfg(feature = "master-node")]
-use rust_extensions::date_time::DateTimeAsMicroseconds;
-use std::collections::{btree_map::Values, BTreeMap};
+use rust_extensions::{date_time::DateTimeAsMicroseconds, lazy::LazyVec};
+#[cfg(feature = "master-node")]
+use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{btree_map::Values, BTreeMap, BTreeSet},
+    ops::Bound,
+};
 
 use crate::db::DbPartition;
+#[cfg(feature = "master-node")]
+use crate::db::DbRow;
+
+pub enum DirtyPartitionState<'s> {
+    Changed(&'s DbPartition),
+    Removed,
+}
 
 pub struct DbPartitionsContainer {
     partitions: BTreeMap<String, DbPartition>,
+    dirty_partitions: BTreeSet<String>,
+    #[cfg(feature = "master-node")]
+    partitions_to_expire_index: crate::AnyExpirationIndex<String>,
+    // Partitions by the expiration moment of their earliest row
+    #[cfg(feature = "master-node")]
+    rows_to_expire_index: crate::AnyExpirationIndex<String>,
     #[cfg(feature = "master-node")]
-    partitions_to_expire_index: crate::ExpirationIndex<String>,
+    rows_to_expire_moments: HashMap<String, DateTimeAsMicroseconds>,
 }
 
 impl DbPartitionsContainer {
     pub fn new() -> Self {
         Self {
             partitions: BTreeMap::new(),
+            dirty_partitions: BTreeSet::new(),
             #[cfg(feature = "master-node")]
-            partitions_to_expire_index: crate::ExpirationIndex::new(),
+            partitions_to_expire_index: crate::AnyExpirationIndex::new(Default::default()),
+            #[cfg(feature = "master-node")]
+            rows_to_expire_index: crate::AnyExpirationIndex::new(Default::default()),
+            #[cfg(feature = "master-node")]
+            rows_to_expire_moments: HashMap::new(),
+        }
+    }
+
+    #[cfg(feature = "master-node")]
+    pub fn new_with_expiration_index(expiration_index: crate::ExpirationIndexKind) -> Self {
+        Self {
+            partitions: BTreeMap::new(),
+            dirty_partitions: BTreeSet::new(),
+            partitions_to_expire_index: crate::AnyExpirationIndex::new(expiration_index),
+            rows_to_expire_index: crate::AnyExpirationIndex::new(expiration_index),
+            rows_to_expire_moments: HashMap::new(),
         }
     }
 
@@ -27,11 +61,14 @@ impl DbPartitionsContainer {
         self.partitions.values()
     }
 
-    pub fn get_partitions_mut(
-        &mut self,
-    ) -> std::collections::btree_map::ValuesMut<String, DbPartition> {
-        self.partitions.values_mut()
+    /// Moves the rows read since the previous call to their places in LRU indexes
+    #[cfg(feature = "master-node")]
+    pub fn update_lru_indexes(&mut self) {
+        for db_partition in self.partitions.values_mut() {
+            db_partition.rows.update_lru_index();
+        }
     }
+
     #[cfg(feature = "master-node")]
     pub fn get_partitions_to_expire(&self, now: DateTimeAsMicroseconds) -> Option<Vec<&String>> {
         self.partitions_to_expire_index.get_items_to_expire(now)
@@ -41,12 +78,110 @@ impl DbPartitionsContainer {
         &self.partitions
     }
 
+    pub fn iter_partitions_by_prefix<'s, 'p>(
+        &'s self,
+        partition_key_prefix: &'p str,
+    ) -> impl Iterator<Item = (&'s String, &'s DbPartition)> + 'p
+    where
+        's: 'p,
+    {
+        self.partitions
+            .range::<str, _>((Bound::Included(partition_key_prefix), Bound::Unbounded))
+            .take_while(move |(partition_key, _)| partition_key.starts_with(partition_key_prefix))
+    }
+
+    pub fn has_dirty_partitions(&self) -> bool {
+        !self.dirty_partitions.is_empty()
+    }
+
+    /// Marks partition to be persisted. Also used if persistence of the drained partition has failed
+    pub fn mark_dirty(&mut self, partition_key: &str) {
+        self.dirty_partitions.insert(partition_key.to_string());
+    }
+
+    #[cfg(feature = "master-node")]
+    fn update_rows_expiration(&mut self, partition_key: &str) {
+        let new_expires = self
+            .partitions
+            .get(partition_key)
+            .and_then(|db_partition| db_partition.get_earliest_row_expiration());
+
+        let old_expires = match new_expires {
+            Some(new_expires) => self
+                .rows_to_expire_moments
+                .insert(partition_key.to_string(), new_expires),
+            None => self.rows_to_expire_moments.remove(partition_key),
+        };
+
+        if old_expires.map(|itm| itm.unix_microseconds)
+            == new_expires.map(|itm| itm.unix_microseconds)
+        {
+            return;
+        }
+
+        self.rows_to_expire_index
+            .update(old_expires, new_expires, &partition_key.to_string());
+    }
+
+    /// Gives partitions changed, created or removed since the previous drain
+    pub fn drain_dirty_partitions(&mut self) -> Option<Vec<(String, DirtyPartitionState<'_>)>> {
+        if self.dirty_partitions.is_empty() {
+            return None;
+        }
+
+        let dirty_partitions = std::mem::take(&mut self.dirty_partitions);
+
+        let result = dirty_partitions
+            .into_iter()
+            .map(|partition_key| {
+                let state = match self.partitions.get(&partition_key) {
+                    Some(db_partition) => DirtyPartitionState::Changed(db_partition),
+                    None => DirtyPartitionState::Removed,
+                };
+
+                (partition_key, state)
+            })
+            .collect();
+
+        Some(result)
+    }
+
     pub fn get(&self, partition_key: &str) -> Option<&DbPartition> {
         self.partitions.get(partition_key)
     }
 
-    pub fn get_mut(&mut self, partition_key: &str) -> Option<&mut DbPartition> {
-        self.partitions.get_mut(partition_key)
+    /// Changes the partition and refreshes its places in the expiration indexes.
+    /// Does not mark the partition dirty. Callers which change the content have to call mark_dirty
+    pub fn update<TResult>(
+        &mut self,
+        partition_key: &str,
+        update: impl FnOnce(&mut DbPartition) -> TResult,
+    ) -> Option<TResult> {
+        let db_partition = self.partitions.get_mut(partition_key)?;
+
+        #[cfg(feature = "master-node")]
+        let old_expires = db_partition.expires;
+
+        let result = update(db_partition);
+
+        #[cfg(feature = "master-node")]
+        {
+            let new_expires = db_partition.expires;
+
+            if old_expires.map(|itm| itm.unix_microseconds)
+                != new_expires.map(|itm| itm.unix_microseconds)
+            {
+                self.partitions_to_expire_index.update(
+                    old_expires,
+                    new_expires,
+                    &partition_key.to_string(),
+                );
+            }
+
+            self.update_rows_expiration(partition_key);
+        }
+
+        Some(result)
     }
 
     pub fn has_partition(&self, partition_key: &str) -> bool {
@@ -54,6 +189,12 @@ impl DbPartitionsContainer {
     }
 
     pub fn insert(&mut self, partition_key: &String, db_partition: DbPartition) {
+        self.init(partition_key, db_partition);
+        self.dirty_partitions.insert(partition_key.to_string());
+    }
+
+    /// Puts the partition loaded from the storage. It's already persisted, so it's not marked dirty
+    pub fn init(&mut self, partition_key: &String, db_partition: DbPartition) {
         #[cfg(feature = "master-node")]
         let new_expires = db_partition.expires;
 
@@ -69,14 +210,23 @@ impl DbPartitionsContainer {
         #[cfg(feature = "master-node")]
         self.partitions_to_expire_index
             .add(new_expires, partition_key);
+
+        #[cfg(feature = "master-node")]
+        self.update_rows_expiration(partition_key);
     }
 
     pub fn remove(&mut self, partition_key: &String) -> Option<DbPartition> {
         let removed_partition = self.partitions.remove(partition_key);
+
+        if removed_partition.is_some() {
+            self.dirty_partitions.insert(partition_key.to_string());
+        }
+
         #[cfg(feature = "master-node")]
         if let Some(removed_partition) = &removed_partition {
             self.partitions_to_expire_index
                 .remove(removed_partition.expires, partition_key);
+            self.update_rows_expiration(partition_key);
         }
 
         removed_partition
@@ -90,12 +240,89 @@ impl DbPartitionsContainer {
         let mut result = BTreeMap::new();
 
         std::mem::swap(&mut result, &mut self.partitions);
+
+        for partition_key in result.keys() {
+            self.dirty_partitions.insert(partition_key.to_string());
+        }
+
         #[cfg(feature = "master-node")]
-        self.partitions_to_expire_index.clear();
+        {
+            self.partitions_to_expire_index.clear();
+            self.rows_to_expire_index.clear();
+            self.rows_to_expire_moments.clear();
+        }
 
         Some(result)
     }
 
+    /// Returns the previous expiration or None if there is no partition
+    #[cfg(feature = "master-node")]
+    pub fn update_expiration(
+        &mut self,
+        partition_key: &str,
+        expires: Option<DateTimeAsMicroseconds>,
+    ) -> Option<Option<DateTimeAsMicroseconds>> {
+        let old_expires = self.update(partition_key, |db_partition| {
+            std::mem::replace(&mut db_partition.expires, expires)
+        })?;
+
+        self.dirty_partitions.insert(partition_key.to_string());
+
+        Some(old_expires)
+    }
+
+    /// Removes the expired partitions in one pass through the expiration index
+    #[cfg(feature = "master-node")]
+    pub fn drain_partitions_to_expire(
+        &mut self,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<Vec<(String, DbPartition)>> {
+        let partitions_to_expire = self.partitions_to_expire_index.drain_items_to_expire(now)?;
+
+        let mut result = LazyVec::new();
+
+        for partition_key in partitions_to_expire {
+            if let Some(removed_partition) = self.partitions.remove(&partition_key) {
+                self.dirty_partitions.insert(partition_key.to_string());
+                self.update_rows_expiration(&partition_key);
+                result.add((partition_key, removed_partition));
+            }
+        }
+
+        result.get_result()
+    }
+
+    /// Removes the expired rows of the partitions found by the index of their earliest rows.
+    /// Returns the removed rows by partition
+    #[cfg(feature = "master-node")]
+    pub fn expire_rows(
+        &mut self,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<Vec<(String, Vec<Arc<DbRow>>)>> {
+        let partitions_to_expire = self.rows_to_expire_index.drain_items_to_expire(now)?;
+
+        let mut result = LazyVec::new();
+
+        for partition_key in partitions_to_expire {
+            self.rows_to_expire_moments.remove(&partition_key);
+
+            let removed_rows = match self.partitions.get_mut(&partition_key) {
+                Some(db_partition) => db_partition.expire_rows(now),
+                None => None,
+            };
+
+            // Partition goes back to the index with its next row to expire
+            self.update_rows_expiration(&partition_key);
+
+            if let Some(removed_rows) = removed_rows {
+                self.dirty_partitions.insert(partition_key.to_string());
+                result.add((partition_key, removed_rows));
+            }
+        }
+
+        result.get_result()
+    }
+
     #[cfg(feature = "master-node")]
     pub fn get_partitions_to_gc_by_max_amount(
         &self,
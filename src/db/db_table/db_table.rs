@@ -1,16 +1,24 @@
 use my_json::json_writer::JsonArrayWriter;
 #[cfg(feature = "master-node")]
 use rust_extensions::date_time::DateTimeAsMicroseconds;
+use rust_extensions::lazy::LazyVec;
 use std::{
-    collections::{btree_map::Values, BTreeMap},
+    collections::{btree_map::Values, BTreeMap, HashMap},
+    ops::Bound,
     sync::Arc,
 };
 
-use crate::db::{DbPartition, DbRow};
+use crate::{
+    db::{DbPartition, DbRow},
+    db_filter::FilterExpression,
+};
 
-use super::DbPartitionsContainer;
 #[cfg(feature = "master-node")]
 use super::DbTableAttributes;
+use super::{
+    ContinuationToken, ContinuationTokenError, DbPartitionsContainer, DbTableChangeEvent,
+    DbTableChangeSink,
+};
 
 pub struct DbTable {
     pub name: String,
@@ -19,6 +27,16 @@ pub struct DbTable {
     pub last_write_moment: DateTimeAsMicroseconds,
     #[cfg(feature = "master-node")]
     pub attributes: DbTableAttributes,
+    pub(crate) change_sink: Option<Arc<dyn DbTableChangeSink>>,
+    pub(crate) sequence: u64,
+    #[cfg(feature = "master-node")]
+    pub(crate) tombstones: super::DbTombstones,
+    // Partitions read with sliding expiration since the last apply_sliding_expiration
+    #[cfg(feature = "master-node")]
+    pub(crate) partitions_to_extend: std::sync::Mutex<std::collections::HashSet<String>>,
+    // Captured on creation, so changing the attributes never mixes index kinds within the table
+    #[cfg(feature = "master-node")]
+    expiration_index: crate::ExpirationIndexKind,
 }
 
 impl DbTable {
@@ -27,9 +45,43 @@ impl DbTable {
         Self {
             name,
             partitions: DbPartitionsContainer::new(),
+            change_sink: None,
+            sequence: 0,
+        }
+    }
+
+    #[cfg(feature = "master-node")]
+    pub fn new(name: String, attributes: DbTableAttributes) -> Self {
+        Self {
+            name,
+            partitions: DbPartitionsContainer::new_with_expiration_index(
+                attributes.expiration_index,
+            ),
+            last_write_moment: DateTimeAsMicroseconds::now(),
+            expiration_index: attributes.expiration_index,
+            attributes,
+            change_sink: None,
+            sequence: 0,
+            tombstones: super::DbTombstones::new(),
+            partitions_to_extend: std::sync::Mutex::new(std::collections::HashSet::new()),
         }
     }
 
+    pub fn set_change_sink(&mut self, change_sink: Option<Arc<dyn DbTableChangeSink>>) {
+        self.change_sink = change_sink;
+    }
+
+    pub(crate) fn emit_change(&self, event: DbTableChangeEvent) {
+        if let Some(change_sink) = &self.change_sink {
+            change_sink.on_change(&self.name, event);
+        }
+    }
+
+    /// Increases with every change of the table. Unlike last_write_moment never goes backwards
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
     pub fn get_partitions_amount(&self) -> usize {
         self.partitions.len()
     }
@@ -88,9 +140,204 @@ impl DbTable {
         json_array_writer.into()
     }
 
-    #[inline]
-    pub fn get_partition_mut(&mut self, partition_key: &str) -> Option<&mut DbPartition> {
-        self.partitions.get_mut(partition_key)
+    pub fn get_table_as_projected_json_array<TName: AsRef<str>>(
+        &self,
+        fields: &[TName],
+    ) -> JsonArrayWriter {
+        let mut json_array_writer = JsonArrayWriter::new();
+
+        for db_partition in self.partitions.get_partitions() {
+            db_partition.fill_with_projected_json_data(&mut json_array_writer, fields);
+        }
+
+        json_array_writer
+    }
+
+    pub fn get_partition_as_projected_json_array<TName: AsRef<str>>(
+        &self,
+        partition_key: &str,
+        fields: &[TName],
+    ) -> Option<JsonArrayWriter> {
+        let mut json_array_writer = JsonArrayWriter::new();
+
+        if let Some(db_partition) = self.partitions.get(partition_key) {
+            db_partition.fill_with_projected_json_data(&mut json_array_writer, fields);
+        }
+
+        json_array_writer.into()
+    }
+
+    pub fn get_table_as_json_array_page(
+        &self,
+        continuation_token: Option<&ContinuationToken>,
+        page_size: usize,
+    ) -> (JsonArrayWriter, Option<ContinuationToken>) {
+        // Page can not be empty, otherwise we would not be able to produce next continuation token
+        let page_size = page_size.max(1);
+
+        let mut json_array_writer = JsonArrayWriter::new();
+
+        let from_partition_key = match continuation_token {
+            Some(continuation_token) => Bound::Included(continuation_token.partition_key.as_str()),
+            None => Bound::Unbounded,
+        };
+
+        let mut last_db_row: Option<&Arc<DbRow>> = None;
+        let mut amount = 0;
+
+        for (partition_key, db_partition) in self
+            .partitions
+            .get_all()
+            .range::<str, _>((from_partition_key, Bound::Unbounded))
+        {
+            let from_row_key = match continuation_token {
+                Some(continuation_token) if &continuation_token.partition_key == partition_key => {
+                    Bound::Excluded(continuation_token.row_key.as_str())
+                }
+                _ => Bound::Unbounded,
+            };
+
+            for db_row in db_partition
+                .rows
+                .iter_rows_range(from_row_key, Bound::Unbounded)
+            {
+                if amount == page_size {
+                    let last_db_row = last_db_row.unwrap();
+                    let next_token =
+                        ContinuationToken::new(&last_db_row.partition_key, &last_db_row.row_key);
+
+                    return (json_array_writer, Some(next_token));
+                }
+
+                json_array_writer.write_raw_element(db_row.data.as_slice());
+                last_db_row = Some(db_row);
+                amount += 1;
+            }
+        }
+
+        (json_array_writer, None)
+    }
+
+    pub fn get_partition_as_json_array_page(
+        &self,
+        partition_key: &str,
+        continuation_token: Option<&ContinuationToken>,
+        page_size: usize,
+    ) -> Result<(JsonArrayWriter, Option<ContinuationToken>), ContinuationTokenError> {
+        if let Some(continuation_token) = continuation_token {
+            if continuation_token.partition_key != partition_key {
+                return Err(ContinuationTokenError::PartitionKeyMismatch {
+                    expected: partition_key.to_string(),
+                    actual: continuation_token.partition_key.to_string(),
+                });
+            }
+        }
+
+        let page_size = page_size.max(1);
+
+        let mut json_array_writer = JsonArrayWriter::new();
+
+        let db_partition = match self.partitions.get(partition_key) {
+            Some(db_partition) => db_partition,
+            None => return Ok((json_array_writer, None)),
+        };
+
+        let from_row_key = match continuation_token {
+            Some(continuation_token) => Bound::Excluded(continuation_token.row_key.as_str()),
+            None => Bound::Unbounded,
+        };
+
+        let mut last_db_row: Option<&Arc<DbRow>> = None;
+
+        for (amount, db_row) in db_partition
+            .rows
+            .iter_rows_range(from_row_key, Bound::Unbounded)
+            .enumerate()
+        {
+            if amount == page_size {
+                let last_db_row = last_db_row.unwrap();
+                let next_token =
+                    ContinuationToken::new(&last_db_row.partition_key, &last_db_row.row_key);
+
+                return Ok((json_array_writer, Some(next_token)));
+            }
+
+            json_array_writer.write_raw_element(db_row.data.as_slice());
+            last_db_row = Some(db_row);
+        }
+
+        Ok((json_array_writer, None))
+    }
+
+    pub fn get_rows_by_prefix<'s>(
+        &'s self,
+        partition_key_prefix: Option<&str>,
+        row_key_prefix: &str,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        let mut result = LazyVec::new();
+
+        let db_rows = self
+            .partitions
+            .iter_partitions_by_prefix(partition_key_prefix.unwrap_or(""))
+            .flat_map(|(_, db_partition)| db_partition.rows.iter_rows_by_prefix(row_key_prefix));
+
+        for db_row in db_rows
+            .skip(skip.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+        {
+            result.add(db_row);
+        }
+
+        result.get_result()
+    }
+
+    pub fn get_rows_by_filter<'s>(
+        &'s self,
+        partition_key: Option<&str>,
+        filter: &FilterExpression,
+        skip: Option<usize>,
+        limit: Option<usize>,
+    ) -> Option<Vec<&'s Arc<DbRow>>> {
+        if let Some(partition_key) = partition_key {
+            let db_partition = self.partitions.get(partition_key)?;
+            return db_partition.get_rows_by_filter(filter, skip, limit);
+        }
+
+        let mut result = LazyVec::new();
+
+        for db_row in self
+            .partitions
+            .get_partitions()
+            .flat_map(|db_partition| db_partition.get_all_rows())
+            .filter(|db_row| filter.matches(db_row))
+            .skip(skip.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+        {
+            result.add(db_row);
+        }
+
+        result.get_result()
+    }
+
+    /// Rows which were inserted or replaced after the change with the given sequence
+    pub fn get_rows_changed_since(&self, sequence: u64) -> Option<Vec<&Arc<DbRow>>> {
+        if self.sequence <= sequence {
+            return None;
+        }
+
+        let mut result = LazyVec::new();
+
+        for db_partition in self.partitions.get_partitions() {
+            if let Some(db_rows) = db_partition.get_rows_changed_since(sequence) {
+                for db_row in db_rows {
+                    result.add(db_row);
+                }
+            }
+        }
+
+        result.get_result()
     }
 
     #[inline]
@@ -106,15 +353,48 @@ impl DbTable {
 /// Insert Operations
 
 impl DbTable {
+    #[cfg(not(feature = "master-node"))]
     #[inline]
-    pub fn insert_or_replace_row(
+    pub fn insert_or_replace_row(&mut self, db_row: &Arc<DbRow>) -> Option<Arc<DbRow>> {
+        self.do_insert_or_replace_row(db_row)
+    }
+
+    #[cfg(not(feature = "master-node"))]
+    #[inline]
+    pub fn insert_row(&mut self, db_row: &Arc<DbRow>) -> bool {
+        self.do_insert_row(db_row)
+    }
+
+    #[cfg(not(feature = "master-node"))]
+    #[inline]
+    pub fn bulk_insert_or_replace(
+        &mut self,
+        partition_key: &String,
+        db_rows: &[Arc<DbRow>],
+    ) -> Option<Vec<Arc<DbRow>>> {
+        self.do_bulk_insert_or_replace(partition_key, db_rows)
+    }
+
+    /// Master node checks the rows against the schema before it gets here
+    pub(crate) fn do_insert_or_replace_row(
         &mut self,
         db_row: &Arc<DbRow>,
         #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
     ) -> Option<Arc<DbRow>> {
+        #[cfg(feature = "master-node")]
+        let db_row = &self.apply_default_row_ttl(db_row, set_last_write_moment);
+
+        self.sequence += 1;
+
+        #[cfg(feature = "master-node")]
+        self.tombstones
+            .remove_row(&db_row.partition_key, &db_row.row_key);
+
         if !self.partitions.has_partition(&db_row.partition_key) {
-            let mut db_partition = DbPartition::new();
+            let mut db_partition = self.create_partition();
             db_partition.insert_or_replace_row(db_row.clone());
+            db_partition.set_row_sequence(&db_row.row_key, self.sequence);
+            db_partition.set_sequence(self.sequence);
 
             self.partitions.insert(&db_row.partition_key, db_partition);
 
@@ -123,72 +403,231 @@ impl DbTable {
                 self.last_write_moment = set_last_write_moment;
             }
 
+            self.emit_change(DbTableChangeEvent::RowInserted(db_row.clone()));
+
             return None;
         }
 
-        let db_partition = self.partitions.get_mut(&db_row.partition_key).unwrap();
-        let removed_db_row = db_partition.insert_or_replace_row(db_row.clone());
+        let sequence = self.sequence;
+
+        let removed_db_row = self
+            .partitions
+            .update(&db_row.partition_key, |db_partition| {
+                let removed_db_row = db_partition.insert_or_replace_row(db_row.clone());
+                db_partition.set_row_sequence(&db_row.row_key, sequence);
+                db_partition.set_sequence(sequence);
+
+                #[cfg(feature = "master-node")]
+                if let Some(set_last_write_moment) = set_last_write_moment {
+                    db_partition.last_write_moment = set_last_write_moment;
+                }
+
+                removed_db_row
+            })
+            .unwrap();
 
         #[cfg(feature = "master-node")]
         if let Some(set_last_write_moment) = set_last_write_moment {
             self.last_write_moment = set_last_write_moment;
-            db_partition.last_write_moment = set_last_write_moment;
         }
 
+        self.partitions.mark_dirty(&db_row.partition_key);
+
+        self.emit_row_upsert(removed_db_row.clone(), db_row);
+
         removed_db_row
     }
 
-    #[inline]
-    pub fn insert_row(
+    pub(crate) fn do_insert_row(
         &mut self,
         db_row: &Arc<DbRow>,
         #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
     ) -> bool {
+        #[cfg(feature = "master-node")]
+        let db_row = &self.apply_default_row_ttl(db_row, set_last_write_moment);
+
         if !self.partitions.has_partition(&db_row.partition_key) {
-            self.partitions
-                .insert(&db_row.partition_key, DbPartition::new());
+            let db_partition = self.create_partition();
+            self.partitions.insert(&db_row.partition_key, db_partition);
         }
 
-        let db_partition = self.partitions.get_mut(&db_row.partition_key).unwrap();
+        let sequence = self.sequence + 1;
+
+        let result = self
+            .partitions
+            .update(&db_row.partition_key, |db_partition| {
+                let result = db_partition.insert_row(db_row.clone());
+
+                if result {
+                    db_partition.set_row_sequence(&db_row.row_key, sequence);
+                    db_partition.set_sequence(sequence);
+
+                    #[cfg(feature = "master-node")]
+                    if let Some(set_last_write_moment) = set_last_write_moment {
+                        db_partition.last_write_moment = set_last_write_moment;
+                    }
+                }
+
+                result
+            })
+            .unwrap();
+
+        if result {
+            self.sequence = sequence;
+
+            #[cfg(feature = "master-node")]
+            self.tombstones
+                .remove_row(&db_row.partition_key, &db_row.row_key);
+        }
 
-        let result = db_partition.insert_row(db_row.clone());
         #[cfg(feature = "master-node")]
+        if result && set_last_write_moment.is_some() {
+            self.last_write_moment = DateTimeAsMicroseconds::now();
+        }
+
         if result {
-            if let Some(set_last_write_moment) = set_last_write_moment {
-                self.last_write_moment = DateTimeAsMicroseconds::now();
-                db_partition.last_write_moment = set_last_write_moment;
-            }
+            self.partitions.mark_dirty(&db_row.partition_key);
+            self.emit_change(DbTableChangeEvent::RowInserted(db_row.clone()));
         }
 
         result
     }
 
-    #[inline]
-    pub fn bulk_insert_or_replace(
+    pub(crate) fn do_bulk_insert_or_replace(
         &mut self,
         partition_key: &String,
         db_rows: &[Arc<DbRow>],
         #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
     ) -> Option<Vec<Arc<DbRow>>> {
+        if db_rows.is_empty() {
+            return None;
+        }
+
+        #[cfg(feature = "master-node")]
+        let db_rows = &self.apply_default_row_ttl_to_rows(db_rows, set_last_write_moment)[..];
+
         if !self.partitions.has_partition(partition_key) {
-            self.partitions.insert(partition_key, DbPartition::new());
+            let db_partition = self.create_partition();
+            self.partitions.insert(partition_key, db_partition);
         }
 
-        let db_partition = self.partitions.get_mut(partition_key).unwrap();
+        self.sequence += 1;
+
+        #[cfg(feature = "master-node")]
+        for db_row in db_rows {
+            self.tombstones
+                .remove_row(&db_row.partition_key, &db_row.row_key);
+        }
+
+        let sequence = self.sequence;
+
+        let result = self
+            .partitions
+            .update(partition_key, |db_partition| {
+                db_partition.set_sequence(sequence);
+
+                let result = db_partition.insert_or_replace_rows_bulk(db_rows);
+
+                for db_row in db_rows {
+                    db_partition.set_row_sequence(&db_row.row_key, sequence);
+                }
+
+                #[cfg(feature = "master-node")]
+                if let Some(set_last_write_moment) = set_last_write_moment {
+                    db_partition.last_write_moment = set_last_write_moment;
+                }
+
+                result
+            })
+            .unwrap();
 
-        let result = db_partition.insert_or_replace_rows_bulk(db_rows);
         #[cfg(feature = "master-node")]
         if let Some(set_last_write_moment) = set_last_write_moment {
             self.last_write_moment = set_last_write_moment;
-            db_partition.last_write_moment = set_last_write_moment;
+        }
+
+        self.partitions.mark_dirty(partition_key);
+
+        if self.change_sink.is_some() {
+            self.emit_bulk_upsert(db_rows, result.as_ref());
         }
 
         result
     }
 
     #[inline]
+    fn create_partition(&self) -> DbPartition {
+        #[cfg(feature = "master-node")]
+        return DbPartition::new_with_expiration_index(self.expiration_index);
+
+        #[cfg(not(feature = "master-node"))]
+        DbPartition::new()
+    }
+
+    /// Puts the partition restored from the storage into the table. Emits no change events
+    /// and does not mark the partition dirty: the table is being loaded, its content is not a change
     pub fn init_partition(&mut self, partition_key: String, db_partition: DbPartition) {
-        self.partitions.insert(&partition_key, db_partition);
+        self.partitions.init(&partition_key, db_partition);
+    }
+
+    // Rows of the bulk with the same key replace each other. Row which was in the partition
+    // before the bulk is replaced by the first of them, so it is among the removed rows only
+    // if there are as many removed rows with the key as there are written ones
+    fn emit_bulk_upsert(&self, db_rows: &[Arc<DbRow>], removed_db_rows: Option<&Vec<Arc<DbRow>>>) {
+        let mut amounts: HashMap<&str, (usize, usize)> = HashMap::new();
+
+        for db_row in db_rows {
+            amounts.entry(db_row.row_key.as_str()).or_default().0 += 1;
+        }
+
+        for removed_db_row in removed_db_rows.into_iter().flatten() {
+            amounts
+                .entry(removed_db_row.row_key.as_str())
+                .or_default()
+                .1 += 1;
+        }
+
+        let mut previous: HashMap<&str, Arc<DbRow>> = HashMap::new();
+
+        for removed_db_row in removed_db_rows.into_iter().flatten() {
+            let row_key = removed_db_row.row_key.as_str();
+            let (written, removed) = amounts[row_key];
+
+            if written == removed && !previous.contains_key(row_key) {
+                previous.insert(row_key, removed_db_row.clone());
+            }
+        }
+
+        for db_row in db_rows {
+            let removed_db_row = previous.insert(db_row.row_key.as_str(), db_row.clone());
+            self.emit_row_upsert(removed_db_row, db_row);
+        }
+    }
+
+    pub(crate) fn emit_partition_removed(&self, partition_key: &str, db_partition: &DbPartition) {
+        if self.change_sink.is_none() {
+            return;
+        }
+
+        for db_row in db_partition.get_all_rows() {
+            self.emit_change(DbTableChangeEvent::RowRemoved(db_row.clone()));
+        }
+
+        self.emit_change(DbTableChangeEvent::PartitionRemoved {
+            partition_key: partition_key.to_string(),
+        });
+    }
+
+    fn emit_row_upsert(&self, removed_db_row: Option<Arc<DbRow>>, db_row: &Arc<DbRow>) {
+        let event = match removed_db_row {
+            Some(old) => DbTableChangeEvent::RowUpdated {
+                old,
+                new: db_row.clone(),
+            },
+            None => DbTableChangeEvent::RowInserted(db_row.clone()),
+        };
+
+        self.emit_change(event);
     }
 }
 
@@ -204,21 +643,43 @@ impl DbTable {
         delete_empty_partition: bool,
         #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
     ) -> Option<(Arc<DbRow>, bool)> {
-        let (removed_row, partition_is_empty) = {
-            let db_partition = self.partitions.get_mut(partition_key)?;
+        let sequence = self.sequence + 1;
 
-            let removed_row = db_partition.remove_row(row_key)?;
-            #[cfg(feature = "master-node")]
-            if let Some(set_last_write_moment) = set_last_write_moment {
-                self.last_write_moment = DateTimeAsMicroseconds::now();
-                db_partition.last_write_moment = set_last_write_moment;
-            }
+        let (removed_row, partition_is_empty) = self
+            .partitions
+            .update(partition_key, |db_partition| {
+                let removed_row = db_partition.remove_row(row_key)?;
 
-            (removed_row, db_partition.is_empty())
-        };
+                db_partition.set_sequence(sequence);
+
+                #[cfg(feature = "master-node")]
+                if let Some(set_last_write_moment) = set_last_write_moment {
+                    db_partition.last_write_moment = set_last_write_moment;
+                }
+
+                Some((removed_row, db_partition.is_empty()))
+            })
+            .flatten()?;
+
+        self.sequence = sequence;
+
+        #[cfg(feature = "master-node")]
+        if set_last_write_moment.is_some() {
+            self.last_write_moment = DateTimeAsMicroseconds::now();
+        }
+
+        self.partitions.mark_dirty(partition_key);
+
+        #[cfg(feature = "master-node")]
+        self.add_tombstones([&removed_row].into_iter(), set_last_write_moment);
+
+        self.emit_change(DbTableChangeEvent::RowRemoved(removed_row.clone()));
 
         if delete_empty_partition && partition_is_empty {
             self.partitions.remove(partition_key);
+            self.emit_change(DbTableChangeEvent::PartitionRemoved {
+                partition_key: partition_key.to_string(),
+            });
         }
 
         return Some((removed_row, partition_is_empty));
@@ -231,22 +692,45 @@ impl DbTable {
         delete_empty_partition: bool,
         #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
     ) -> Option<(Vec<Arc<DbRow>>, bool)> {
-        let (removed_rows, partition_is_empty) = {
-            let db_partition = self.partitions.get_mut(partition_key)?;
+        let sequence = self.sequence + 1;
 
-            let removed_rows = db_partition.remove_rows_bulk(row_keys)?;
+        let (removed_rows, partition_is_empty) = self
+            .partitions
+            .update(partition_key, |db_partition| {
+                let removed_rows = db_partition.remove_rows_bulk(row_keys)?;
 
-            #[cfg(feature = "master-node")]
-            if let Some(set_last_write_moment) = set_last_write_moment {
-                self.last_write_moment = DateTimeAsMicroseconds::now();
-                db_partition.last_write_moment = set_last_write_moment;
-            }
+                db_partition.set_sequence(sequence);
 
-            (removed_rows, db_partition.is_empty())
-        };
+                #[cfg(feature = "master-node")]
+                if let Some(set_last_write_moment) = set_last_write_moment {
+                    db_partition.last_write_moment = set_last_write_moment;
+                }
+
+                Some((removed_rows, db_partition.is_empty()))
+            })
+            .flatten()?;
+
+        self.sequence = sequence;
+
+        #[cfg(feature = "master-node")]
+        if set_last_write_moment.is_some() {
+            self.last_write_moment = DateTimeAsMicroseconds::now();
+        }
+
+        self.partitions.mark_dirty(partition_key);
+
+        #[cfg(feature = "master-node")]
+        self.add_tombstones(removed_rows.iter(), set_last_write_moment);
+
+        for removed_row in &removed_rows {
+            self.emit_change(DbTableChangeEvent::RowRemoved(removed_row.clone()));
+        }
 
         if delete_empty_partition && partition_is_empty {
             self.partitions.remove(partition_key);
+            self.emit_change(DbTableChangeEvent::PartitionRemoved {
+                partition_key: partition_key.to_string(),
+            });
         }
 
         return Some((removed_rows, partition_is_empty));
@@ -260,6 +744,18 @@ impl DbTable {
     ) -> Option<DbPartition> {
         let removed_partition = self.partitions.remove(partition_key);
 
+        if removed_partition.is_some() {
+            self.sequence += 1;
+        }
+
+        #[cfg(feature = "master-node")]
+        if removed_partition.is_some() {
+            self.add_tombstone(
+                super::DbTombstoneRange::Partition(partition_key.to_string()),
+                set_last_write_moment,
+            );
+        }
+
         #[cfg(feature = "master-node")]
         if removed_partition.is_some() {
             if let Some(set_last_write_moment) = set_last_write_moment {
@@ -267,10 +763,23 @@ impl DbTable {
             }
         }
 
+        if let Some(removed_partition) = &removed_partition {
+            self.emit_partition_removed(partition_key, removed_partition);
+        }
+
         removed_partition
     }
 
     pub fn clear_table(&mut self) -> Option<BTreeMap<String, DbPartition>> {
-        self.partitions.clear()
+        let result = self.partitions.clear()?;
+
+        self.sequence += 1;
+
+        #[cfg(feature = "master-node")]
+        self.add_tombstone(super::DbTombstoneRange::Table, None);
+
+        self.emit_change(DbTableChangeEvent::TableCleared);
+
+        Some(result)
     }
 }
@@ -1,11 +1,35 @@
+use std::time::Duration;
+
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
+use crate::{validations::DbTableSchema, ExpirationIndexKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingExpiration {
+    /// Row expires after this time since the last read
+    pub duration: Duration,
+    /// Partition expiration is moved forward as well
+    pub extend_partition: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct DbTableAttributes {
     pub persist: bool,
     pub max_partitions_amount: Option<usize>,
     pub max_rows_per_partition_amount: Option<usize>,
     pub created: DateTimeAsMicroseconds,
+    /// Tombstones of deleted rows are kept only if retention is set
+    pub tombstones_retention: Option<Duration>,
+    /// Budgets of the content size in bytes. GC removes the least recently read data to fit them
+    pub max_table_content_size: Option<usize>,
+    pub max_partition_content_size: Option<usize>,
+    pub sliding_expiration: Option<SlidingExpiration>,
+    /// Rows inserted without Expires expire after this time
+    pub default_row_ttl: Option<Duration>,
+    /// Is picked when the table is created. Changing it later does not rebuild the indexes
+    pub expiration_index: ExpirationIndexKind,
+    /// Rows are checked against it by the insert operations
+    pub schema: Option<DbTableSchema>,
 }
 
 impl DbTableAttributes {
@@ -15,6 +39,13 @@ impl DbTableAttributes {
             persist: true,
             max_partitions_amount: None,
             max_rows_per_partition_amount: None,
+            tombstones_retention: None,
+            max_table_content_size: None,
+            max_partition_content_size: None,
+            sliding_expiration: None,
+            default_row_ttl: None,
+            expiration_index: ExpirationIndexKind::BTree,
+            schema: None,
         }
     }
 }
@@ -31,6 +62,13 @@ impl DbTableAttributes {
             created,
             max_partitions_amount,
             max_rows_per_partition_amount,
+            tombstones_retention: None,
+            max_table_content_size: None,
+            max_partition_content_size: None,
+            sliding_expiration: None,
+            default_row_ttl: None,
+            expiration_index: ExpirationIndexKind::BTree,
+            schema: None,
         }
     }
 
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::db::DbRow;
+
+pub enum DbTableChangeEvent {
+    RowInserted(Arc<DbRow>),
+    RowUpdated {
+        old: Arc<DbRow>,
+        new: Arc<DbRow>,
+    },
+    RowRemoved(Arc<DbRow>),
+    /// Comes after RowRemoved of every row the partition had, whatever has removed it
+    PartitionRemoved {
+        partition_key: String,
+    },
+    PartitionExpirationUpdated {
+        partition_key: String,
+        expires: Option<DateTimeAsMicroseconds>,
+    },
+    /// All the partitions and rows are removed. Comes without RowRemoved and PartitionRemoved events
+    TableCleared,
+}
+
+/// Partition is removed the same way by every operation - delete, GC or expiration:
+/// RowRemoved for each of its rows and then PartitionRemoved
+pub trait DbTableChangeSink: Send + Sync {
+    fn on_change(&self, table_name: &str, event: DbTableChangeEvent);
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::db::DbRow;
+
+use super::{ConditionalOperationError, DbTable};
+
+/// Operations which are applied only if the TimeStamp of the row in the table is the same as expected one (ETag)
+impl DbTable {
+    pub fn conditional_replace_row(
+        &mut self,
+        db_row: &Arc<DbRow>,
+        expected_time_stamp: &str,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<Arc<DbRow>, ConditionalOperationError> {
+        self.check_time_stamp(&db_row.partition_key, &db_row.row_key, expected_time_stamp)?;
+
+        let removed_db_row = self.insert_or_replace_row(db_row, set_last_write_moment)?;
+
+        Ok(removed_db_row.unwrap())
+    }
+
+    pub fn conditional_remove_row(
+        &mut self,
+        partition_key: &String,
+        row_key: &str,
+        expected_time_stamp: &str,
+        delete_empty_partition: bool,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<(Arc<DbRow>, bool), ConditionalOperationError> {
+        self.check_time_stamp(partition_key, row_key, expected_time_stamp)?;
+
+        let result = self.remove_row(
+            partition_key,
+            row_key,
+            delete_empty_partition,
+            set_last_write_moment,
+        );
+
+        Ok(result.unwrap())
+    }
+
+    /// Rows are removed only if all of them have expected TimeStamps. Otherwise nothing is removed
+    pub fn conditional_bulk_remove_rows<'s, TIter: Iterator<Item = (&'s String, &'s str)>>(
+        &mut self,
+        partition_key: &String,
+        row_keys_with_time_stamps: TIter,
+        delete_empty_partition: bool,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<(Vec<Arc<DbRow>>, bool), ConditionalOperationError> {
+        let mut row_keys = Vec::new();
+
+        for (row_key, expected_time_stamp) in row_keys_with_time_stamps {
+            self.check_time_stamp(partition_key, row_key, expected_time_stamp)?;
+            row_keys.push(row_key);
+        }
+
+        let result = self.bulk_remove_rows(
+            partition_key,
+            row_keys.into_iter(),
+            delete_empty_partition,
+            set_last_write_moment,
+        );
+
+        Ok(result.unwrap_or_default())
+    }
+
+    fn check_time_stamp(
+        &self,
+        partition_key: &str,
+        row_key: &str,
+        expected_time_stamp: &str,
+    ) -> Result<(), ConditionalOperationError> {
+        let db_row = self
+            .partitions
+            .get(partition_key)
+            .and_then(|db_partition| db_partition.get_row(row_key));
+
+        let db_row = match db_row {
+            Some(db_row) => db_row,
+            None => {
+                return Err(ConditionalOperationError::RowNotFound {
+                    partition_key: partition_key.to_string(),
+                    row_key: row_key.to_string(),
+                })
+            }
+        };
+
+        if db_row.time_stamp != expected_time_stamp {
+            return Err(ConditionalOperationError::TimeStampMismatch {
+                partition_key: partition_key.to_string(),
+                row_key: row_key.to_string(),
+                expected: expected_time_stamp.to_string(),
+                actual: db_row.time_stamp.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use crate::{
+        db_json_entity::JsonTimeStamp,
+        test_utils::{create_db_row_at, create_db_table},
+    };
+
+    use super::ConditionalOperationError;
+
+    #[test]
+    fn test_conditional_replace() {
+        let mut db_table = create_db_table();
+
+        let first_time_stamp =
+            JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(1_000_000));
+        db_table
+            .insert_row(&create_db_row_at("pk", "rk", &first_time_stamp), None)
+            .unwrap();
+
+        let second_time_stamp =
+            JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(2_000_000));
+        let db_row = create_db_row_at("pk", "rk", &second_time_stamp);
+
+        let result = db_table.conditional_replace_row(&db_row, first_time_stamp.as_str(), None);
+        assert!(result.is_ok());
+
+        // Second writer still has the first TimeStamp
+        let db_row = create_db_row_at("pk", "rk", &JsonTimeStamp::now());
+        let result = db_table.conditional_replace_row(&db_row, first_time_stamp.as_str(), None);
+
+        assert!(matches!(
+            result,
+            Err(ConditionalOperationError::TimeStampMismatch { actual, .. }) if actual == second_time_stamp.as_str()
+        ));
+    }
+
+    #[test]
+    fn test_conditional_bulk_remove_is_all_or_nothing() {
+        let mut db_table = create_db_table();
+
+        let time_stamp = JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(1_000_000));
+        db_table
+            .insert_row(&create_db_row_at("pk", "rk1", &time_stamp), None)
+            .unwrap();
+        db_table
+            .insert_row(&create_db_row_at("pk", "rk2", &time_stamp), None)
+            .unwrap();
+
+        let partition_key = "pk".to_string();
+        let rk1 = "rk1".to_string();
+        let rk2 = "rk2".to_string();
+        let rk3 = "rk3".to_string();
+
+        let result = db_table.conditional_bulk_remove_rows(
+            &partition_key,
+            [(&rk1, time_stamp.as_str()), (&rk3, time_stamp.as_str())].into_iter(),
+            true,
+            None,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ConditionalOperationError::RowNotFound { row_key, .. }) if row_key == "rk3"
+        ));
+        assert_eq!(2, db_table.get_rows_amount());
+
+        let (removed_rows, partition_is_empty) = db_table
+            .conditional_bulk_remove_rows(
+                &partition_key,
+                [(&rk1, time_stamp.as_str()), (&rk2, time_stamp.as_str())].into_iter(),
+                true,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(2, removed_rows.len());
+        assert!(partition_is_empty);
+        assert_eq!(0, db_table.get_partitions_amount());
+    }
+}
@@ -0,0 +1,28 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::db::DbRow;
+
+#[derive(Default)]
+pub struct DbTableGcReport {
+    /// Partitions removed as a whole. Partitions which became empty after the rows GC are here as well
+    pub removed_partitions: Vec<String>,
+    pub removed_rows: BTreeMap<String, Vec<Arc<DbRow>>>,
+    pub removed_tombstones: usize,
+    pub bytes_freed: usize,
+}
+
+impl DbTableGcReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed_partitions.is_empty()
+            && self.removed_rows.is_empty()
+            && self.removed_tombstones == 0
+    }
+
+    pub fn get_removed_rows_amount(&self) -> usize {
+        self.removed_rows.values().map(|itm| itm.len()).sum()
+    }
+}
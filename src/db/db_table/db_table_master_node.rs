@@ -1,19 +1,335 @@
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
 
-use rust_extensions::date_time::DateTimeAsMicroseconds;
+use rust_extensions::{date_time::DateTimeAsMicroseconds, lazy::LazyVec};
 
-use super::{DataToGc, DbPartitionsContainer, DbTable, DbTableAttributes};
+use crate::db::{sliding_expiration_is_due, DbRow};
+
+use super::{
+    DataToGc, DbTable, DbTableChangeEvent, DbTableGcReport, DbTombstone, DbTombstoneRange,
+    DbTombstones,
+};
 
 impl DbTable {
-    pub fn new(name: String, attributes: DbTableAttributes) -> Self {
-        Self {
-            name,
-            partitions: DbPartitionsContainer::new(),
-            last_write_moment: DateTimeAsMicroseconds::now(),
-            attributes,
+    /// Rows without Expires get one according to the default TTL of the table.
+    /// Expires is injected into the JSON, so the JSON stays the source of truth
+    pub(crate) fn apply_default_row_ttl(
+        &self,
+        db_row: &Arc<DbRow>,
+        now: Option<DateTimeAsMicroseconds>,
+    ) -> Arc<DbRow> {
+        let default_row_ttl = match self.attributes.default_row_ttl {
+            Some(default_row_ttl) => default_row_ttl,
+            None => return db_row.clone(),
+        };
+
+        if db_row.expires.is_some() {
+            return db_row.clone();
+        }
+
+        let now = now.unwrap_or_else(DateTimeAsMicroseconds::now);
+        let expires =
+            DateTimeAsMicroseconds::new(now.unix_microseconds + default_row_ttl.as_micros() as i64);
+
+        Arc::new(db_row.create_with_new_expiration_time(Some(expires)))
+    }
+
+    pub(crate) fn apply_default_row_ttl_to_rows<'s>(
+        &self,
+        db_rows: &'s [Arc<DbRow>],
+        now: Option<DateTimeAsMicroseconds>,
+    ) -> Cow<'s, [Arc<DbRow>]> {
+        if self.attributes.default_row_ttl.is_none()
+            || db_rows.iter().all(|db_row| db_row.expires.is_some())
+        {
+            return Cow::Borrowed(db_rows);
+        }
+
+        let now = now.unwrap_or_else(DateTimeAsMicroseconds::now);
+
+        Cow::Owned(
+            db_rows
+                .iter()
+                .map(|db_row| self.apply_default_row_ttl(db_row, Some(now)))
+                .collect(),
+        )
+    }
+
+    /// Returns the previous expiration or None if there is no partition
+    pub fn update_partition_expiration(
+        &mut self,
+        partition_key: &str,
+        expires: Option<DateTimeAsMicroseconds>,
+    ) -> Option<Option<DateTimeAsMicroseconds>> {
+        let result = self.partitions.update_expiration(partition_key, expires)?;
+
+        self.sequence += 1;
+        let sequence = self.sequence;
+        self.partitions.update(partition_key, |db_partition| {
+            db_partition.set_sequence(sequence);
+        });
+
+        self.emit_change(DbTableChangeEvent::PartitionExpirationUpdated {
+            partition_key: partition_key.to_string(),
+            expires,
+        });
+
+        Some(result)
+    }
+
+    /// Returns the previous expirations of the partitions found
+    pub fn update_partitions_expiration<
+        'p,
+        TItems: Iterator<Item = (&'p str, Option<DateTimeAsMicroseconds>)>,
+    >(
+        &mut self,
+        items: TItems,
+    ) -> Option<Vec<(String, Option<DateTimeAsMicroseconds>)>> {
+        let mut result = LazyVec::new();
+
+        for (partition_key, expires) in items {
+            if let Some(old_expires) = self.update_partition_expiration(partition_key, expires) {
+                result.add((partition_key.to_string(), old_expires));
+            }
+        }
+
+        result.get_result()
+    }
+
+    /// Returns the replaced row. None if there is no row or its expiration is the same
+    pub fn update_row_expiration(
+        &mut self,
+        partition_key: &str,
+        row_key: &str,
+        expires: Option<DateTimeAsMicroseconds>,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Option<Arc<DbRow>> {
+        let sequence = self.sequence + 1;
+
+        let (removed_db_row, new_db_row) = self
+            .partitions
+            .update(partition_key, |db_partition| {
+                let result = db_partition.update_row_expiration(row_key, expires)?;
+
+                db_partition.set_row_sequence(row_key, sequence);
+                db_partition.set_sequence(sequence);
+
+                if let Some(set_last_write_moment) = set_last_write_moment {
+                    db_partition.last_write_moment = set_last_write_moment;
+                }
+
+                Some(result)
+            })
+            .flatten()?;
+
+        self.sequence = sequence;
+
+        if let Some(set_last_write_moment) = set_last_write_moment {
+            self.last_write_moment = set_last_write_moment;
+        }
+
+        self.partitions.mark_dirty(partition_key);
+
+        self.emit_change(DbTableChangeEvent::RowUpdated {
+            old: removed_db_row.clone(),
+            new: new_db_row,
+        });
+
+        Some(removed_db_row)
+    }
+
+    /// Reads the row. If table has sliding expiration - row expiration is queued to be moved forward
+    pub fn get_row_and_extend_expiration(
+        &self,
+        partition_key: &str,
+        row_key: &str,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<&Arc<DbRow>> {
+        let db_partition = self.partitions.get(partition_key)?;
+
+        if self.attributes.sliding_expiration.is_none() {
+            let db_row = db_partition
+                .rows
+                .get_and_update_last_read_access(row_key, now)?;
+            db_partition.update_last_read_moment(now);
+            return Some(db_row);
+        }
+
+        let sliding_expiration = self.attributes.sliding_expiration.unwrap();
+
+        let db_row = db_partition.get_row_and_extend_expiration(
+            row_key,
+            now,
+            sliding_expiration.duration,
+        )?;
+
+        let extend_partition = sliding_expiration.extend_partition
+            && sliding_expiration_is_due(db_partition.expires, now, sliding_expiration.duration);
+
+        if extend_partition
+            || sliding_expiration_is_due(db_row.expires, now, sliding_expiration.duration)
+        {
+            self.partitions_to_extend
+                .lock()
+                .unwrap()
+                .insert(partition_key.to_string());
+        }
+
+        Some(db_row)
+    }
+
+    /// Moves expiration of the rows and partitions read since the previous call.
+    /// Expiration indexes are updated here in batch, so reads stay cheap. Extended rows come as RowUpdated
+    /// and extended partitions as PartitionExpirationUpdated, both under the new sequence.
+    /// Returns amount of updated rows
+    pub fn apply_sliding_expiration(&mut self) -> usize {
+        let partitions_to_extend = std::mem::take(self.partitions_to_extend.get_mut().unwrap());
+
+        let sliding_expiration = match self.attributes.sliding_expiration {
+            Some(sliding_expiration) => sliding_expiration,
+            None => return 0,
+        };
+
+        let sequence = self.sequence + 1;
+        let mut updated_rows = Vec::new();
+        let mut extended_partitions = Vec::new();
+
+        for partition_key in partitions_to_extend {
+            let extended = self.partitions.update(&partition_key, |db_partition| {
+                let rows = db_partition.apply_sliding_expiration(sliding_expiration.duration);
+
+                if let Some(rows) = &rows {
+                    db_partition.set_sequence(sequence);
+
+                    for (_, new_db_row) in rows {
+                        db_partition.set_row_sequence(&new_db_row.row_key, sequence);
+                    }
+                }
+
+                if !sliding_expiration.extend_partition {
+                    return (rows, None);
+                }
+
+                let last_read_moment = db_partition.get_last_read_moment();
+
+                if !sliding_expiration_is_due(
+                    db_partition.expires,
+                    last_read_moment,
+                    sliding_expiration.duration,
+                ) {
+                    return (rows, None);
+                }
+
+                db_partition.set_sequence(sequence);
+
+                let expires = DateTimeAsMicroseconds::new(
+                    last_read_moment.unix_microseconds
+                        + sliding_expiration.duration.as_micros() as i64,
+                );
+
+                (rows, Some(expires))
+            });
+
+            let Some((rows, partition_expires)) = extended else {
+                continue;
+            };
+
+            if let Some(expires) = partition_expires {
+                self.partitions
+                    .update_expiration(&partition_key, Some(expires));
+                extended_partitions.push((partition_key.clone(), expires));
+            }
+
+            if let Some(rows) = rows {
+                updated_rows.extend(rows);
+                self.partitions.mark_dirty(&partition_key);
+            }
+        }
+
+        if updated_rows.is_empty() && extended_partitions.is_empty() {
+            return 0;
+        }
+
+        self.sequence = sequence;
+
+        let result = updated_rows.len();
+
+        for (old, new) in updated_rows {
+            self.emit_change(DbTableChangeEvent::RowUpdated { old, new });
+        }
+
+        for (partition_key, expires) in extended_partitions {
+            self.emit_change(DbTableChangeEvent::PartitionExpirationUpdated {
+                partition_key,
+                expires: Some(expires),
+            });
+        }
+
+        result
+    }
+
+    pub fn get_tombstones(&self) -> &DbTombstones {
+        &self.tombstones
+    }
+
+    pub fn get_tombstones_since(&self, sequence: u64) -> Option<Vec<&DbTombstone>> {
+        self.tombstones.get_since(sequence)
+    }
+
+    /// Removes tombstones deleted before the moment. Returns amount of purged tombstones
+    pub fn purge_tombstones(&mut self, deleted_before: DateTimeAsMicroseconds) -> usize {
+        self.tombstones.purge(deleted_before)
+    }
+
+    /// Removes tombstones found by get_data_to_gc. Tombstones written again since then stay.
+    /// Returns amount of removed tombstones
+    pub fn gc_tombstones(&mut self, tombstones_to_gc: &[DbTombstone]) -> usize {
+        let mut result = 0;
+
+        for tombstone in tombstones_to_gc {
+            if self
+                .tombstones
+                .remove_with_sequence(&tombstone.range, tombstone.sequence)
+                .is_some()
+            {
+                result += 1;
+            }
+        }
+
+        result
+    }
+
+    pub(crate) fn add_tombstones<'s, TRows: Iterator<Item = &'s Arc<DbRow>>>(
+        &mut self,
+        db_rows: TRows,
+        deleted: Option<DateTimeAsMicroseconds>,
+    ) {
+        for db_row in db_rows {
+            self.add_tombstone(
+                DbTombstoneRange::Row {
+                    partition_key: db_row.partition_key.to_string(),
+                    row_key: db_row.row_key.to_string(),
+                },
+                deleted,
+            );
         }
     }
 
+    pub(crate) fn add_tombstone(
+        &mut self,
+        range: DbTombstoneRange,
+        deleted: Option<DateTimeAsMicroseconds>,
+    ) {
+        if self.attributes.tombstones_retention.is_none() {
+            return;
+        }
+
+        self.tombstones.add(DbTombstone {
+            range,
+            deleted: deleted.unwrap_or_else(DateTimeAsMicroseconds::now),
+            sequence: self.sequence,
+        });
+    }
+
     pub fn get_expiration_index_rows_amount(&self) -> usize {
         let mut result = 0;
 
@@ -54,55 +370,241 @@ impl DbTable {
             }
         }
 
+        // Content size of the partitions which stay after GC
+        let mut partitions_content_size = Vec::new();
+
         //Find DbRows to expire
         for (partition_key, db_partition) in self.partitions.get_all() {
             if result.has_partition_to_gc(partition_key) {
                 continue;
             }
 
+            let mut rows_to_gc = BTreeMap::new();
+
             if let Some(rows_to_expire) = db_partition.get_rows_to_expire(now) {
-                result.add_rows_to_expire(
-                    partition_key,
-                    rows_to_expire.iter().map(|itm| itm.row_key.to_string()),
-                );
+                for db_row in rows_to_expire {
+                    rows_to_gc.insert(db_row.row_key.as_str(), db_row);
+                }
             }
 
             //Find DBRows to GC by max amount
             if let Some(max_rows_per_partition) = self.attributes.max_rows_per_partition_amount {
-                if let Some(rows_to_gc) = db_partition
+                if let Some(rows_to_gc_by_max_amount) = db_partition
                     .rows
                     .get_rows_to_gc_by_max_amount(max_rows_per_partition)
                 {
-                    result.add_rows_to_expire(
-                        partition_key,
-                        rows_to_gc.iter().map(|itm| itm.row_key.to_string()),
-                    );
+                    for db_row in rows_to_gc_by_max_amount {
+                        rows_to_gc.insert(db_row.row_key.as_str(), db_row);
+                    }
+                }
+            }
+
+            let mut content_size = db_partition.get_content_size()
+                - rows_to_gc
+                    .values()
+                    .map(|db_row| db_row.data.len())
+                    .sum::<usize>();
+
+            //Find DBRows to GC by max content size
+            if let Some(max_partition_content_size) = self.attributes.max_partition_content_size {
+                for db_row in db_partition.rows.iter_least_recently_read() {
+                    if content_size <= max_partition_content_size {
+                        break;
+                    }
+
+                    if rows_to_gc.insert(db_row.row_key.as_str(), db_row).is_none() {
+                        content_size -= db_row.data.len();
+                    }
+                }
+            }
+
+            if !rows_to_gc.is_empty() {
+                result.add_rows_to_expire(
+                    partition_key,
+                    rows_to_gc.keys().map(|row_key| row_key.to_string()),
+                );
+            }
+
+            partitions_content_size.push((
+                db_partition.get_last_read_moment().unix_microseconds,
+                partition_key,
+                content_size,
+            ));
+        }
+
+        //Find partitions to GC by max table content size
+        if let Some(max_table_content_size) = self.attributes.max_table_content_size {
+            let mut table_content_size: usize = partitions_content_size
+                .iter()
+                .map(|(_, _, content_size)| content_size)
+                .sum();
+
+            if table_content_size > max_table_content_size {
+                partitions_content_size.sort();
+
+                for (_, partition_key, content_size) in partitions_content_size {
+                    if table_content_size <= max_table_content_size {
+                        break;
+                    }
+
+                    result.add_partition_to_expire(partition_key);
+                    table_content_size -= content_size;
+                }
+            }
+        }
+
+        if let Some(tombstones_retention) = self.attributes.tombstones_retention {
+            if let Some(tombstones_to_gc) = self.tombstones.get_to_gc(now, tombstones_retention) {
+                for tombstone in tombstones_to_gc {
+                    result.add_tombstone_to_gc(tombstone);
                 }
             }
         }
 
         result
     }
+
+    /// Removes expired partitions and rows straight from the expiration indexes.
+    /// Returns the removed rows by partition
+    pub fn expire_now(
+        &mut self,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<BTreeMap<String, Vec<Arc<DbRow>>>> {
+        let mut result = BTreeMap::new();
+
+        if let Some(expired_partitions) = self.partitions.drain_partitions_to_expire(now) {
+            self.sequence += 1;
+
+            for (partition_key, db_partition) in expired_partitions {
+                self.add_tombstone(
+                    DbTombstoneRange::Partition(partition_key.to_string()),
+                    Some(now),
+                );
+
+                self.emit_partition_removed(&partition_key, &db_partition);
+
+                result.insert(partition_key, db_partition.get_all_rows_cloned());
+            }
+        }
+
+        if let Some(expired_rows) = self.partitions.expire_rows(now) {
+            self.sequence += 1;
+
+            for (partition_key, removed_rows) in expired_rows {
+                let sequence = self.sequence;
+                let partition_is_empty = self
+                    .partitions
+                    .update(&partition_key, |db_partition| {
+                        db_partition.set_sequence(sequence);
+                        db_partition.last_write_moment = now;
+                        db_partition.is_empty()
+                    })
+                    .unwrap_or(false);
+
+                self.add_tombstones(removed_rows.iter(), Some(now));
+
+                for removed_row in &removed_rows {
+                    self.emit_change(DbTableChangeEvent::RowRemoved(removed_row.clone()));
+                }
+
+                if partition_is_empty {
+                    self.partitions.remove(&partition_key);
+                    self.emit_change(DbTableChangeEvent::PartitionRemoved {
+                        partition_key: partition_key.to_string(),
+                    });
+                }
+
+                result.insert(partition_key, removed_rows);
+            }
+        }
+
+        if result.is_empty() {
+            return None;
+        }
+
+        self.last_write_moment = now;
+
+        Some(result)
+    }
+
+    /// Removes the data found by get_data_to_gc. Removal goes the same way as the regular
+    /// delete operations do, so indexes, tombstones and change events stay consistent
+    pub fn apply_gc(
+        &mut self,
+        data_to_gc: DataToGc,
+        now: DateTimeAsMicroseconds,
+    ) -> DbTableGcReport {
+        let mut report = DbTableGcReport::new();
+
+        self.partitions.update_lru_indexes();
+
+        let data_to_gc = match data_to_gc.get_data_to_gc() {
+            Some(data_to_gc) => data_to_gc,
+            None => return report,
+        };
+
+        for partition_key in data_to_gc.partitions.keys() {
+            if let Some(removed_partition) = self.remove_partition(partition_key, Some(now)) {
+                report.bytes_freed += removed_partition.get_content_size();
+                report.removed_partitions.push(partition_key.to_string());
+            }
+        }
+
+        for (partition_key, row_keys) in &data_to_gc.db_rows {
+            if data_to_gc.partitions.contains_key(partition_key) {
+                continue;
+            }
+
+            if let Some((removed_rows, partition_is_empty)) =
+                self.bulk_remove_rows(partition_key, row_keys.iter(), true, Some(now))
+            {
+                for db_row in &removed_rows {
+                    report.bytes_freed += db_row.data.len();
+                }
+
+                report
+                    .removed_rows
+                    .insert(partition_key.to_string(), removed_rows);
+
+                if partition_is_empty {
+                    report.removed_partitions.push(partition_key.to_string());
+                }
+            }
+        }
+
+        report.removed_tombstones = self.gc_tombstones(&data_to_gc.tombstones);
+
+        report
+    }
 }
 
 #[cfg(feature = "master-node")]
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
+
+    use my_json::{
+        json_reader::array_parser::ArrayToJsonObjectsSplitter, json_writer::JsonArrayWriter,
+    };
 
     use crate::{
-        db::DbTable,
+        db::{
+            ContinuationToken, ContinuationTokenError, DbTable, DbTableAttributes,
+            DbTableChangeEvent, DbTableChangeSink, DirtyPartitionState,
+        },
+        db_filter::FilterExpression,
         db_json_entity::{DbJsonEntity, JsonTimeStamp},
+        test_utils::{
+            create_db_row, create_db_row_at, create_db_row_with_fields, create_db_table,
+            insert_test_row,
+        },
     };
 
     use super::*;
 
     #[test]
     fn test_insert_record() {
-        let mut db_table = DbTable::new(
-            "test-table".to_string(),
-            DbTableAttributes::create_default(),
-        );
+        let mut db_table = create_db_table();
 
         let now = JsonTimeStamp::now();
 
@@ -117,7 +619,7 @@ mod tests {
 
         let db_row = Arc::new(db_row);
 
-        db_table.insert_row(&db_row, None);
+        db_table.insert_row(&db_row, None).unwrap();
 
         assert_eq!(db_table.get_table_size(), db_row.data.len());
         assert_eq!(db_table.get_partitions_amount(), 1);
@@ -125,10 +627,7 @@ mod tests {
 
     #[test]
     fn test_insert_and_insert_or_replace() {
-        let mut db_table = DbTable::new(
-            "test-table".to_string(),
-            DbTableAttributes::create_default(),
-        );
+        let mut db_table = create_db_table();
 
         let now = JsonTimeStamp::now();
 
@@ -143,7 +642,7 @@ mod tests {
 
         let db_row = Arc::new(db_row);
 
-        db_table.insert_row(&db_row, None);
+        db_table.insert_row(&db_row, None).unwrap();
 
         let test_json = r#"{
             "PartitionKey": "test",
@@ -157,9 +656,1066 @@ mod tests {
 
         let db_row2 = Arc::new(db_row2);
 
-        db_table.insert_or_replace_row(&db_row2, None);
+        db_table.insert_or_replace_row(&db_row2, None).unwrap();
 
         assert_eq!(db_table.get_table_size(), db_row2.data.len());
         assert_eq!(db_table.get_partitions_amount(), 1);
     }
+
+    #[test]
+    fn test_get_rows_by_prefix_across_partitions() {
+        let mut db_table = create_db_table();
+
+        let now = JsonTimeStamp::now();
+
+        for (partition_key, row_key) in [
+            ("eu:1", "order:1"),
+            ("eu:1", "order:2"),
+            ("eu:1", "profile"),
+            ("eu:2", "order:1"),
+            ("us:1", "order:1"),
+        ] {
+            db_table
+                .insert_row(&create_db_row_at(partition_key, row_key, &now), None)
+                .unwrap();
+        }
+
+        let result = db_table
+            .get_rows_by_prefix(Some("eu:"), "order:", None, None)
+            .unwrap();
+
+        let result: Vec<(&str, &str)> = result
+            .iter()
+            .map(|itm| (itm.partition_key.as_str(), itm.row_key.as_str()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("eu:1", "order:1"),
+                ("eu:1", "order:2"),
+                ("eu:2", "order:1")
+            ],
+            result
+        );
+
+        let result = db_table
+            .get_rows_by_prefix(None, "order:", Some(2), Some(2))
+            .unwrap();
+
+        let result: Vec<(&str, &str)> = result
+            .iter()
+            .map(|itm| (itm.partition_key.as_str(), itm.row_key.as_str()))
+            .collect();
+
+        assert_eq!(vec![("eu:2", "order:1"), ("us:1", "order:1")], result);
+    }
+
+    #[test]
+    fn test_get_table_as_json_array_page() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+        insert_test_row(&mut db_table, "pk2", "rk3");
+
+        let (json_array_writer, continuation_token) =
+            db_table.get_table_as_json_array_page(None, 2);
+        assert_eq!(vec!["pk1/rk1", "pk1/rk2"], get_page_keys(json_array_writer));
+        let continuation_token = continuation_token.unwrap();
+        assert_eq!(ContinuationToken::new("pk1", "rk2"), continuation_token);
+
+        // Changes between pages must not break the resuming
+        db_table.remove_row(&"pk1".to_string(), "rk2", true, None);
+        insert_test_row(&mut db_table, "pk1", "rk0");
+        insert_test_row(&mut db_table, "pk2", "rk2");
+
+        let (json_array_writer, continuation_token) =
+            db_table.get_table_as_json_array_page(Some(&continuation_token), 2);
+        assert_eq!(vec!["pk2/rk1", "pk2/rk2"], get_page_keys(json_array_writer));
+        let continuation_token = continuation_token.unwrap();
+        assert_eq!(ContinuationToken::new("pk2", "rk2"), continuation_token);
+
+        let (json_array_writer, continuation_token) =
+            db_table.get_table_as_json_array_page(Some(&continuation_token), 2);
+        assert_eq!(vec!["pk2/rk3"], get_page_keys(json_array_writer));
+        assert!(continuation_token.is_none());
+    }
+
+    #[test]
+    fn test_get_partition_as_json_array_page() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk1", "rk3");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        let (json_array_writer, continuation_token) = db_table
+            .get_partition_as_json_array_page("pk1", None, 2)
+            .unwrap();
+        assert_eq!(vec!["pk1/rk1", "pk1/rk2"], get_page_keys(json_array_writer));
+        let continuation_token = continuation_token.unwrap();
+        assert_eq!(ContinuationToken::new("pk1", "rk2"), continuation_token);
+
+        let (json_array_writer, next_token) = db_table
+            .get_partition_as_json_array_page("pk1", Some(&continuation_token), 2)
+            .unwrap();
+        assert_eq!(vec!["pk1/rk3"], get_page_keys(json_array_writer));
+        assert!(next_token.is_none());
+
+        let result = db_table.get_partition_as_json_array_page("pk2", Some(&continuation_token), 2);
+
+        assert!(matches!(
+            result,
+            Err(ContinuationTokenError::PartitionKeyMismatch { expected, actual })
+                if expected == "pk2" && actual == "pk1"
+        ));
+    }
+
+    fn get_page_keys(json_array_writer: JsonArrayWriter) -> Vec<String> {
+        let json = json_array_writer.build();
+
+        json.as_slice()
+            .split_array_json_to_objects()
+            .map(|itm| {
+                let db_json_entity = DbJsonEntity::parse(itm.unwrap()).unwrap();
+                format!(
+                    "{}/{}",
+                    db_json_entity.partition_key, db_json_entity.row_key
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_rows_by_filter() {
+        let mut db_table = create_db_table();
+
+        for (partition_key, row_key, status, amount) in [
+            ("pk1", "rk1", "Active", 50),
+            ("pk1", "rk2", "Active", 150),
+            ("pk2", "rk1", "Blocked", 200),
+            ("pk2", "rk2", "Active", 300),
+        ] {
+            let fields = format!(r#""Status":"{}","Amount":{}"#, status, amount);
+            db_table
+                .insert_row(
+                    &create_db_row_with_fields(partition_key, row_key, &fields),
+                    None,
+                )
+                .unwrap();
+        }
+
+        let filter = FilterExpression::parse("Status eq 'Active' and Amount gt 100").unwrap();
+
+        let result = db_table
+            .get_rows_by_filter(None, &filter, None, None)
+            .unwrap();
+
+        let result: Vec<(&str, &str)> = result
+            .iter()
+            .map(|itm| (itm.partition_key.as_str(), itm.row_key.as_str()))
+            .collect();
+
+        assert_eq!(vec![("pk1", "rk2"), ("pk2", "rk2")], result);
+
+        let result = db_table
+            .get_rows_by_filter(Some("pk1"), &filter, None, None)
+            .unwrap();
+
+        assert_eq!(1, result.len());
+        assert_eq!("rk2", result[0].row_key);
+    }
+
+    struct TestChangeSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl DbTableChangeSink for TestChangeSink {
+        fn on_change(&self, table_name: &str, event: DbTableChangeEvent) {
+            let event = match event {
+                DbTableChangeEvent::RowInserted(db_row) => {
+                    format!("inserted {}/{}", db_row.partition_key, db_row.row_key)
+                }
+                DbTableChangeEvent::RowUpdated { old, new } => {
+                    assert_eq!(old.row_key, new.row_key);
+                    format!("updated {}/{}", new.partition_key, new.row_key)
+                }
+                DbTableChangeEvent::RowRemoved(db_row) => {
+                    format!("removed {}/{}", db_row.partition_key, db_row.row_key)
+                }
+                DbTableChangeEvent::PartitionRemoved { partition_key } => {
+                    format!("partition_removed {}", partition_key)
+                }
+                DbTableChangeEvent::PartitionExpirationUpdated {
+                    partition_key,
+                    expires,
+                } => {
+                    format!(
+                        "partition_expiration {} {:?}",
+                        partition_key,
+                        expires.map(|itm| itm.unix_microseconds)
+                    )
+                }
+                DbTableChangeEvent::TableCleared => "cleared".to_string(),
+            };
+
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", table_name, event));
+        }
+    }
+
+    #[test]
+    fn test_change_events() {
+        let mut db_table = create_db_table();
+
+        let change_sink = Arc::new(TestChangeSink {
+            events: Mutex::new(Vec::new()),
+        });
+
+        db_table.set_change_sink(Some(change_sink.clone()));
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        let db_row = create_db_row("pk2", "rk1");
+        assert!(!db_table.insert_row(&db_row, None).unwrap());
+
+        db_table
+            .bulk_insert_or_replace(&"pk2".to_string(), &[db_row], None)
+            .unwrap();
+
+        let db_row = create_db_row("pk2", "rk2");
+
+        db_table
+            .bulk_insert_or_replace(&"pk2".to_string(), &[db_row.clone(), db_row], None)
+            .unwrap();
+
+        db_table.update_partition_expiration("pk2", Some(DateTimeAsMicroseconds::new(1)));
+
+        db_table.remove_row(&"pk1".to_string(), "rk1", true, None);
+        db_table.remove_partition(&"pk3".to_string(), None);
+        db_table.remove_partition(&"pk2".to_string(), None);
+
+        insert_test_row(&mut db_table, "pk4", "rk1");
+        db_table.clear_table();
+
+        let events = change_sink.events.lock().unwrap();
+
+        assert_eq!(
+            vec![
+                "test-table: inserted pk1/rk1",
+                "test-table: updated pk1/rk1",
+                "test-table: inserted pk2/rk1",
+                "test-table: updated pk2/rk1",
+                "test-table: inserted pk2/rk2",
+                "test-table: updated pk2/rk2",
+                "test-table: partition_expiration pk2 Some(1)",
+                "test-table: removed pk1/rk1",
+                "test-table: partition_removed pk1",
+                "test-table: removed pk2/rk1",
+                "test-table: removed pk2/rk2",
+                "test-table: partition_removed pk2",
+                "test-table: inserted pk4/rk1",
+                "test-table: cleared",
+            ],
+            *events
+        );
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut db_table = create_db_table();
+
+        assert_eq!(0, db_table.get_sequence());
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        assert_eq!(3, db_table.get_sequence());
+        assert_eq!(2, db_table.get_partition("pk1").unwrap().get_sequence());
+        assert_eq!(3, db_table.get_partition("pk2").unwrap().get_sequence());
+
+        let version = db_table.get_sequence();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        db_table.remove_row(&"pk1".to_string(), "rk2", true, None);
+
+        assert_eq!(5, db_table.get_sequence());
+        assert_eq!(5, db_table.get_partition("pk1").unwrap().get_sequence());
+
+        let result = db_table.get_rows_changed_since(version).unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!("pk1", result[0].partition_key);
+        assert_eq!("rk1", result[0].row_key);
+        assert_eq!(
+            Some(4),
+            db_table
+                .get_partition("pk1")
+                .unwrap()
+                .get_row_sequence("rk1")
+        );
+
+        assert!(db_table.get_rows_changed_since(5).is_none());
+
+        // Rejected insert is not a change
+        let db_row = create_db_row("pk2", "rk1");
+        assert!(!db_table.insert_row(&db_row, None).unwrap());
+        assert_eq!(5, db_table.get_sequence());
+
+        // Empty bulk is not a change
+        assert!(db_table
+            .bulk_insert_or_replace(&"pk2".to_string(), &[], None)
+            .unwrap()
+            .is_none());
+        assert_eq!(5, db_table.get_sequence());
+
+        db_table.clear_table();
+        assert_eq!(6, db_table.get_sequence());
+    }
+
+    #[test]
+    fn test_sequence_of_row_shared_between_tables() {
+        let mut db_table1 = DbTable::new(
+            "test-table1".to_string(),
+            DbTableAttributes::create_default(),
+        );
+
+        let mut db_table2 = DbTable::new(
+            "test-table2".to_string(),
+            DbTableAttributes::create_default(),
+        );
+
+        insert_test_row(&mut db_table2, "pk0", "rk0");
+        insert_test_row(&mut db_table2, "pk0", "rk1");
+
+        let db_row = create_db_row("pk1", "rk1");
+
+        db_table1.insert_row(&db_row, None).unwrap();
+        db_table2.insert_row(&db_row, None).unwrap();
+
+        assert_eq!(1, db_table1.get_rows_changed_since(0).unwrap().len());
+        assert_eq!(1, db_table2.get_rows_changed_since(2).unwrap().len());
+        assert_eq!(
+            Some(1),
+            db_table1
+                .get_partition("pk1")
+                .unwrap()
+                .get_row_sequence("rk1")
+        );
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.tombstones_retention = Some(std::time::Duration::from_secs(10));
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        let version = db_table.get_sequence();
+
+        db_table.remove_row(
+            &"pk1".to_string(),
+            "rk1",
+            true,
+            Some(DateTimeAsMicroseconds::new(1_000_000)),
+        );
+        db_table.remove_partition(
+            &"pk2".to_string(),
+            Some(DateTimeAsMicroseconds::new(20_000_000)),
+        );
+
+        let result = db_table.get_tombstones_since(version).unwrap();
+        assert_eq!(2, result.len());
+        assert_eq!(
+            4,
+            db_table
+                .get_tombstones()
+                .get("pk1", "rk1")
+                .unwrap()
+                .sequence
+        );
+        assert_eq!(
+            5,
+            db_table
+                .get_tombstones()
+                .get_partition("pk2")
+                .unwrap()
+                .get_removed()
+                .unwrap()
+                .sequence
+        );
+
+        // Row is back - its tombstone is gone, range tombstone of the partition stays
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        assert!(db_table.get_tombstones().get("pk1", "rk1").is_none());
+        assert_eq!(1, db_table.get_tombstones().len());
+
+        db_table.remove_row(
+            &"pk1".to_string(),
+            "rk2",
+            true,
+            Some(DateTimeAsMicroseconds::new(30_000_000)),
+        );
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(35_000_000));
+        let tombstones_to_gc = data_to_gc.get_tombstones_to_gc().unwrap();
+        assert_eq!(1, tombstones_to_gc.len());
+        assert_eq!(
+            DbTombstoneRange::Partition("pk2".to_string()),
+            tombstones_to_gc[0].range
+        );
+
+        let tombstones_to_gc = tombstones_to_gc.clone();
+        assert_eq!(1, db_table.gc_tombstones(&tombstones_to_gc));
+        assert_eq!(1, db_table.get_tombstones().len());
+
+        assert_eq!(
+            1,
+            db_table.purge_tombstones(DateTimeAsMicroseconds::new(40_000_000))
+        );
+        assert!(db_table.get_tombstones().is_empty());
+    }
+
+    #[test]
+    fn test_gc_keeps_tombstone_written_after_get_data_to_gc() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.tombstones_retention = Some(std::time::Duration::from_secs(10));
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        db_table.remove_row(
+            &"pk1".to_string(),
+            "rk1",
+            false,
+            Some(DateTimeAsMicroseconds::new(1_000_000)),
+        );
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(20_000_000));
+
+        // Row is written and deleted again before the GC is applied
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        db_table.remove_row(
+            &"pk1".to_string(),
+            "rk1",
+            false,
+            Some(DateTimeAsMicroseconds::new(15_000_000)),
+        );
+        let sequence = db_table.get_sequence();
+
+        let report = db_table.apply_gc(data_to_gc, DateTimeAsMicroseconds::new(20_000_000));
+
+        assert_eq!(0, report.removed_tombstones);
+        assert_eq!(
+            sequence,
+            db_table
+                .get_tombstones()
+                .get("pk1", "rk1")
+                .unwrap()
+                .sequence
+        );
+    }
+
+    #[test]
+    fn test_clear_table_writes_single_tombstone() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.tombstones_retention = Some(std::time::Duration::from_secs(10));
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        db_table.remove_row(&"pk1".to_string(), "rk1", true, None);
+
+        db_table.clear_table();
+
+        assert_eq!(1, db_table.get_tombstones().len());
+        assert_eq!(
+            db_table.get_sequence(),
+            db_table.get_tombstones().get_cleared().unwrap().sequence
+        );
+    }
+
+    #[test]
+    fn test_no_tombstones_without_retention() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        db_table.remove_row(&"pk1".to_string(), "rk1", true, None);
+
+        assert!(db_table.get_tombstones().is_empty());
+        assert!(!db_table
+            .get_data_to_gc(DateTimeAsMicroseconds::now())
+            .has_data_to_gc());
+    }
+
+    #[test]
+    fn test_drain_dirty_partitions() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        let dirty_partitions = db_table.partitions.drain_dirty_partitions().unwrap();
+        assert_eq!(2, dirty_partitions.len());
+        assert!(db_table.partitions.drain_dirty_partitions().is_none());
+
+        insert_test_row(&mut db_table, "pk2", "rk2");
+        insert_test_row(&mut db_table, "pk3", "rk1");
+        db_table.remove_partition(&"pk1".to_string(), None);
+
+        let dirty_partitions = db_table.partitions.drain_dirty_partitions().unwrap();
+
+        let result: Vec<(&str, Option<usize>)> = dirty_partitions
+            .iter()
+            .map(|(partition_key, state)| {
+                let rows_amount = match state {
+                    DirtyPartitionState::Changed(db_partition) => {
+                        Some(db_partition.get_rows_amount())
+                    }
+                    DirtyPartitionState::Removed => None,
+                };
+
+                (partition_key.as_str(), rows_amount)
+            })
+            .collect();
+
+        assert_eq!(
+            vec![("pk1", None), ("pk2", Some(2)), ("pk3", Some(1))],
+            result
+        );
+
+        assert!(!db_table.partitions.has_dirty_partitions());
+    }
+
+    #[test]
+    fn test_access_without_changes_does_not_mark_dirty() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        db_table.partitions.drain_dirty_partitions().unwrap();
+
+        assert!(db_table.partitions.update("pk1", |_| ()).is_some());
+        db_table.partitions.update_lru_indexes();
+        assert!(db_table
+            .remove_row(&"pk1".to_string(), "rk2", true, None)
+            .is_none());
+
+        let db_row = create_db_row("pk1", "rk1");
+        assert!(!db_table.insert_row(&db_row, None).unwrap());
+
+        assert!(!db_table.partitions.has_dirty_partitions());
+
+        db_table.remove_row(&"pk1".to_string(), "rk1", false, None);
+        assert!(db_table.partitions.has_dirty_partitions());
+    }
+
+    fn insert_test_row_read_at(
+        db_table: &mut DbTable,
+        partition_key: &str,
+        row_key: &str,
+        last_read_access: i64,
+    ) -> usize {
+        let time_stamp =
+            JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(last_read_access));
+        let db_row = create_db_row_at(partition_key, row_key, &time_stamp);
+        db_table.insert_or_replace_row(&db_row, None).unwrap();
+
+        db_table
+            .get_partition(partition_key)
+            .unwrap()
+            .update_last_read_moment(DateTimeAsMicroseconds::new(last_read_access));
+
+        db_row.data.len()
+    }
+
+    #[test]
+    fn test_gc_by_partition_content_size() {
+        let mut db_table = create_db_table();
+
+        let row_size = insert_test_row_read_at(&mut db_table, "pk1", "rk1", 3_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk2", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk3", 2_000_000);
+
+        db_table.attributes.max_partition_content_size = Some(row_size * 2);
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::now());
+        let rows_to_gc = data_to_gc.get_rows_to_gc().unwrap();
+
+        assert_eq!(vec!["rk2".to_string()], *rows_to_gc.get("pk1").unwrap());
+    }
+
+    #[test]
+    fn test_gc_by_table_content_size() {
+        let mut db_table = create_db_table();
+
+        let row_size = insert_test_row_read_at(&mut db_table, "pk1", "rk1", 2_000_000);
+        insert_test_row_read_at(&mut db_table, "pk2", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk3", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk4", "rk1", 3_000_000);
+
+        db_table.attributes.max_table_content_size = Some(row_size * 2);
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::now());
+        let partitions_to_gc = data_to_gc.get_partitions_to_gc().unwrap();
+
+        assert_eq!(2, partitions_to_gc.len());
+        assert!(data_to_gc.has_partition_to_gc("pk2"));
+        assert!(data_to_gc.has_partition_to_gc("pk3"));
+    }
+
+    #[test]
+    fn test_apply_gc() {
+        let mut db_table = create_db_table();
+
+        let row_size = insert_test_row_read_at(&mut db_table, "pk1", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk2", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk2", "rk2", 2_000_000);
+        insert_test_row_read_at(&mut db_table, "pk3", "rk1", 3_000_000);
+        insert_test_row_read_at(&mut db_table, "pk3", "rk2", 3_000_000);
+
+        let mut data_to_gc = DataToGc::new();
+        data_to_gc.add_partition_to_expire("pk1");
+        data_to_gc.add_rows_to_expire("pk2", vec!["rk1".to_string()].into_iter());
+        data_to_gc.add_rows_to_expire(
+            "pk3",
+            vec!["rk1".to_string(), "rk2".to_string()].into_iter(),
+        );
+
+        let change_sink = Arc::new(TestChangeSink {
+            events: Mutex::new(Vec::new()),
+        });
+
+        db_table.set_change_sink(Some(change_sink.clone()));
+
+        let report = db_table.apply_gc(data_to_gc, DateTimeAsMicroseconds::new(5_000_000));
+
+        // Rows are removed partition by partition in no particular order
+        let events = change_sink.events.lock().unwrap().clone();
+        let pk3_events: Vec<_> = events.iter().filter(|itm| itm.contains("pk3")).collect();
+
+        assert_eq!(
+            vec![
+                "test-table: removed pk1/rk1",
+                "test-table: partition_removed pk1",
+            ],
+            events[..2]
+        );
+        assert_eq!(
+            vec![
+                "test-table: removed pk3/rk1",
+                "test-table: removed pk3/rk2",
+                "test-table: partition_removed pk3",
+            ],
+            pk3_events
+        );
+        assert!(events.contains(&"test-table: removed pk2/rk1".to_string()));
+        assert_eq!(6, events.len());
+
+        let mut removed_partitions = report.removed_partitions.clone();
+        removed_partitions.sort();
+        assert_eq!(vec!["pk1", "pk3"], removed_partitions);
+
+        assert_eq!(3, report.get_removed_rows_amount());
+        assert_eq!(row_size * 4, report.bytes_freed);
+
+        assert_eq!(1, db_table.get_partitions_amount());
+        assert_eq!(1, db_table.get_rows_amount());
+        assert_eq!(row_size, db_table.get_table_size());
+        assert!(db_table
+            .get_data_to_gc(DateTimeAsMicroseconds::new(5_000_000))
+            .get_data_to_gc()
+            .is_none());
+    }
+
+    #[test]
+    fn test_gc_by_max_rows_amount_follows_reads() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.max_rows_per_partition_amount = Some(3);
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row_read_at(&mut db_table, "pk1", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk2", 2_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk3", 3_000_000);
+
+        let read_and_gc = |db_table: &mut DbTable, row_keys: &[&str], now: i64| {
+            let now = DateTimeAsMicroseconds::new(now);
+
+            for row_key in row_keys {
+                db_table
+                    .get_row_and_extend_expiration("pk1", row_key, now)
+                    .unwrap();
+            }
+
+            let data_to_gc = db_table.get_data_to_gc(now);
+            let report = db_table.apply_gc(data_to_gc, now);
+
+            let mut removed_rows: Vec<_> = report
+                .removed_rows
+                .values()
+                .flatten()
+                .map(|db_row| db_row.row_key.to_string())
+                .collect();
+            removed_rows.sort();
+            removed_rows
+        };
+
+        insert_test_row_read_at(&mut db_table, "pk1", "rk4", 4_000_000);
+        assert_eq!(vec!["rk2"], read_and_gc(&mut db_table, &["rk1"], 5_000_000));
+
+        insert_test_row_read_at(&mut db_table, "pk1", "rk5", 6_000_000);
+        assert_eq!(vec!["rk3"], read_and_gc(&mut db_table, &[], 7_000_000));
+
+        // rk1 was read at the first cycle, rk4 was written later and is not read since
+        insert_test_row_read_at(&mut db_table, "pk1", "rk6", 8_000_000);
+        assert_eq!(vec!["rk1"], read_and_gc(&mut db_table, &["rk4"], 9_000_000));
+
+        insert_test_row_read_at(&mut db_table, "pk1", "rk7", 10_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk8", 11_000_000);
+        assert_eq!(
+            vec!["rk5", "rk6"],
+            read_and_gc(&mut db_table, &["rk4", "rk7"], 12_000_000)
+        );
+
+        let mut row_keys: Vec<_> = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_all_rows()
+            .map(|db_row| db_row.row_key.as_str())
+            .collect();
+        row_keys.sort();
+        assert_eq!(vec!["rk4", "rk7", "rk8"], row_keys);
+    }
+
+    #[test]
+    fn test_sliding_expiration() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.sliding_expiration = Some(crate::db::SlidingExpiration {
+            duration: std::time::Duration::from_secs(10),
+            extend_partition: true,
+        });
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row_read_at(&mut db_table, "pk1", "rk1", 1_000_000);
+        insert_test_row_read_at(&mut db_table, "pk1", "rk2", 1_000_000);
+
+        let change_sink = Arc::new(TestChangeSink {
+            events: Mutex::new(Vec::new()),
+        });
+
+        db_table.set_change_sink(Some(change_sink.clone()));
+
+        let db_row = db_table
+            .get_row_and_extend_expiration("pk1", "rk1", DateTimeAsMicroseconds::new(5_000_000))
+            .unwrap();
+        assert!(db_row.expires.is_none());
+
+        // Nothing is moved until the batch is applied
+        assert_eq!(0, db_table.get_expiration_index_rows_amount());
+
+        let sequence = db_table.get_sequence();
+
+        assert_eq!(1, db_table.apply_sliding_expiration());
+        assert_eq!(0, db_table.apply_sliding_expiration());
+
+        assert_eq!(sequence + 1, db_table.get_sequence());
+        assert_eq!(
+            vec![
+                "test-table: updated pk1/rk1".to_string(),
+                "test-table: partition_expiration pk1 Some(15000000)".to_string(),
+            ],
+            *change_sink.events.lock().unwrap()
+        );
+
+        let db_partition = db_table.get_partition("pk1").unwrap();
+        assert_eq!(sequence + 1, db_partition.get_sequence());
+
+        let db_row = db_partition.get_row("rk1").unwrap();
+        assert_eq!(15_000_000, db_row.expires.unwrap().unix_microseconds);
+        assert_eq!(15_000_000, db_partition.expires.unwrap().unix_microseconds);
+
+        let table_size: usize = db_partition.get_all_rows().map(|itm| itm.data.len()).sum();
+        assert_eq!(table_size, db_table.get_table_size());
+
+        // More than half of the duration is left - the row is not rewritten
+        let extended_db_row = db_row.clone();
+
+        db_table
+            .get_row_and_extend_expiration("pk1", "rk1", DateTimeAsMicroseconds::new(6_000_000))
+            .unwrap();
+
+        assert_eq!(0, db_table.apply_sliding_expiration());
+        assert_eq!(sequence + 1, db_table.get_sequence());
+        assert_eq!(2, change_sink.events.lock().unwrap().len());
+
+        let db_row = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk1")
+            .unwrap();
+        assert!(Arc::ptr_eq(&extended_db_row, db_row));
+
+        // Less than half of the duration is left - the row and the partition are moved
+        db_table
+            .get_row_and_extend_expiration("pk1", "rk1", DateTimeAsMicroseconds::new(11_000_000))
+            .unwrap();
+
+        assert_eq!(1, db_table.apply_sliding_expiration());
+        assert_eq!(sequence + 2, db_table.get_sequence());
+
+        let db_partition = db_table.get_partition("pk1").unwrap();
+        let db_row = db_partition.get_row("rk1").unwrap();
+        assert_eq!(21_000_000, db_row.expires.unwrap().unix_microseconds);
+        assert_eq!(21_000_000, db_partition.expires.unwrap().unix_microseconds);
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(22_000_000));
+        assert!(data_to_gc.has_partition_to_gc("pk1"));
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(20_000_000));
+        assert!(!data_to_gc.has_data_to_gc());
+    }
+
+    #[test]
+    fn test_update_partition_expiration() {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        let old_expires = db_table
+            .update_partition_expiration("pk1", Some(DateTimeAsMicroseconds::new(5_000_000)));
+        assert!(old_expires.unwrap().is_none());
+        assert_eq!(3, db_table.get_sequence());
+        assert_eq!(3, db_table.get_partition("pk1").unwrap().get_sequence());
+
+        assert!(db_table
+            .update_partition_expiration("pk3", Some(DateTimeAsMicroseconds::new(5_000_000)))
+            .is_none());
+        assert_eq!(3, db_table.get_sequence());
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(6_000_000));
+        assert!(data_to_gc.has_partition_to_gc("pk1"));
+
+        let result = db_table
+            .update_partitions_expiration(
+                [
+                    ("pk1", Some(DateTimeAsMicroseconds::new(10_000_000))),
+                    ("pk2", Some(DateTimeAsMicroseconds::new(3_000_000))),
+                    ("pk3", None),
+                ]
+                .into_iter(),
+            )
+            .unwrap();
+
+        assert_eq!(2, result.len());
+        assert_eq!("pk1", result[0].0);
+        assert_eq!(5_000_000, result[0].1.unwrap().unix_microseconds);
+        assert_eq!("pk2", result[1].0);
+        assert!(result[1].1.is_none());
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(6_000_000));
+        assert!(!data_to_gc.has_partition_to_gc("pk1"));
+        assert!(data_to_gc.has_partition_to_gc("pk2"));
+
+        db_table.update_partition_expiration("pk2", None);
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(20_000_000));
+        assert!(data_to_gc.has_partition_to_gc("pk1"));
+        assert!(!data_to_gc.has_partition_to_gc("pk2"));
+    }
+
+    #[test]
+    fn test_default_row_ttl() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.default_row_ttl = Some(std::time::Duration::from_secs(10));
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        let now = DateTimeAsMicroseconds::new(1_000_000);
+        let time_stamp = JsonTimeStamp::from_date_time(now);
+
+        db_table
+            .insert_row(&create_db_row_at("pk1", "rk1", &time_stamp), Some(now))
+            .unwrap();
+
+        let db_json_entity = DbJsonEntity::parse(
+            r#"{"PartitionKey":"pk1","RowKey":"rk2","Expires":"2030-01-01T00:00:00"}"#.as_bytes(),
+        )
+        .unwrap();
+        db_table
+            .insert_or_replace_row(&Arc::new(db_json_entity.new_db_row(&time_stamp)), Some(now))
+            .unwrap();
+
+        db_table
+            .bulk_insert_or_replace(
+                &"pk2".to_string(),
+                &[create_db_row_at("pk2", "rk1", &time_stamp)],
+                Some(now),
+            )
+            .unwrap();
+
+        let db_row = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk1")
+            .unwrap();
+        assert_eq!(11_000_000, db_row.expires.unwrap().unix_microseconds);
+        let db_json_entity = DbJsonEntity::parse(&db_row.data).unwrap();
+        assert!(db_json_entity.expires.is_some());
+
+        let db_row = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk2")
+            .unwrap();
+        assert_eq!(
+            "2030-01-01T00:00:00",
+            &db_row.expires.unwrap().to_rfc3339()[..19]
+        );
+
+        let db_row = db_table
+            .get_partition("pk2")
+            .unwrap()
+            .get_row("rk1")
+            .unwrap();
+        assert_eq!(11_000_000, db_row.expires.unwrap().unix_microseconds);
+
+        assert_eq!(3, db_table.get_expiration_index_rows_amount());
+
+        let data_to_gc = db_table.get_data_to_gc(DateTimeAsMicroseconds::new(12_000_000));
+        assert!(data_to_gc.has_data_to_gc());
+    }
+
+    fn insert_test_row_expires_at(
+        db_table: &mut DbTable,
+        partition_key: &str,
+        row_key: &str,
+        expires: Option<i64>,
+    ) {
+        let db_row = create_db_row(partition_key, row_key);
+        let db_row = match expires {
+            Some(expires) => Arc::new(
+                db_row.create_with_new_expiration_time(Some(DateTimeAsMicroseconds::new(expires))),
+            ),
+            None => db_row,
+        };
+
+        db_table.insert_or_replace_row(&db_row, None).unwrap();
+    }
+
+    #[test]
+    fn test_expire_now() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.tombstones_retention = Some(std::time::Duration::from_secs(60));
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        insert_test_row_expires_at(&mut db_table, "pk1", "rk1", None);
+        insert_test_row_expires_at(&mut db_table, "pk1", "rk2", None);
+        insert_test_row_expires_at(&mut db_table, "pk2", "rk1", Some(2_000_000));
+        insert_test_row_expires_at(&mut db_table, "pk2", "rk2", None);
+        insert_test_row_expires_at(&mut db_table, "pk3", "rk1", Some(1_000_000));
+        insert_test_row_expires_at(&mut db_table, "pk4", "rk1", Some(9_000_000));
+
+        db_table.update_partition_expiration("pk1", Some(DateTimeAsMicroseconds::new(3_000_000)));
+
+        let change_sink = Arc::new(TestChangeSink {
+            events: Mutex::new(Vec::new()),
+        });
+
+        db_table.set_change_sink(Some(change_sink.clone()));
+
+        let sequence = db_table.get_sequence();
+        let now = DateTimeAsMicroseconds::new(3_000_000);
+
+        let result = db_table.expire_now(now).unwrap();
+
+        assert_eq!(vec!["pk1", "pk2", "pk3"], result.keys().collect::<Vec<_>>());
+        assert_eq!(2, result.get("pk1").unwrap().len());
+        assert_eq!("rk1", result.get("pk2").unwrap()[0].row_key);
+        assert_eq!("rk1", result.get("pk3").unwrap()[0].row_key);
+
+        assert!(db_table.get_partition("pk1").is_none());
+        assert!(db_table.get_partition("pk3").is_none());
+
+        assert_eq!(
+            vec![
+                "test-table: removed pk1/rk1",
+                "test-table: removed pk1/rk2",
+                "test-table: partition_removed pk1",
+                "test-table: removed pk3/rk1",
+                "test-table: partition_removed pk3",
+                "test-table: removed pk2/rk1",
+            ],
+            *change_sink.events.lock().unwrap()
+        );
+
+        let db_partition = db_table.get_partition("pk2").unwrap();
+        assert_eq!(1, db_partition.rows_count());
+        assert!(db_partition.get_row("rk2").is_some());
+        assert_eq!(
+            db_partition
+                .get_all_rows()
+                .map(|itm| itm.data.len())
+                .sum::<usize>(),
+            db_partition.get_content_size()
+        );
+
+        assert_eq!(1, db_table.get_expiration_index_rows_amount());
+        assert!(db_table.get_sequence() > sequence);
+        assert_eq!(3, db_table.get_tombstones().len());
+
+        assert!(db_table.expire_now(now).is_none());
+        assert!(!db_table.get_data_to_gc(now).has_data_to_gc());
+
+        let result = db_table
+            .expire_now(DateTimeAsMicroseconds::new(9_000_000))
+            .unwrap();
+        assert_eq!(vec!["pk4"], result.keys().collect::<Vec<_>>());
+        assert!(db_table.get_partition("pk4").is_none());
+    }
+
+    #[test]
+    fn test_expire_now_follows_row_expiration_updates() {
+        let mut db_table = create_db_table();
+
+        insert_test_row_expires_at(&mut db_table, "pk1", "rk1", Some(1_000_000));
+        insert_test_row_expires_at(&mut db_table, "pk1", "rk2", Some(5_000_000));
+
+        db_table.update_row_expiration(
+            "pk1",
+            "rk1",
+            Some(DateTimeAsMicroseconds::new(3_000_000)),
+            None,
+        );
+
+        assert!(db_table
+            .expire_now(DateTimeAsMicroseconds::new(2_000_000))
+            .is_none());
+
+        let result = db_table
+            .expire_now(DateTimeAsMicroseconds::new(3_000_000))
+            .unwrap();
+        assert_eq!("rk1", result.get("pk1").unwrap()[0].row_key);
+
+        let result = db_table
+            .expire_now(DateTimeAsMicroseconds::new(5_000_000))
+            .unwrap();
+        assert_eq!("rk2", result.get("pk1").unwrap()[0].row_key);
+        assert!(db_table.get_partition("pk1").is_none());
+    }
 }
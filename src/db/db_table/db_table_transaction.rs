@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "master-node")]
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::{DbTable, DbTransaction, DbTransactionError, DbTransactionResult, DbTransactionStep};
+
+impl DbTable {
+    /// All the steps are validated before the first one is applied. If validation fails - table stays untouched
+    pub fn apply_transaction(
+        &mut self,
+        transaction: DbTransaction,
+        #[cfg(feature = "master-node")] set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<DbTransactionResult, DbTransactionError> {
+        self.validate_transaction(&transaction)?;
+
+        let mut result = DbTransactionResult::new();
+
+        // Rows are checked against the schema by validate_transaction
+        for step in transaction.steps {
+            match step {
+                DbTransactionStep::InsertRow(db_row) => {
+                    self.do_insert_row(
+                        &db_row,
+                        #[cfg(feature = "master-node")]
+                        set_last_write_moment,
+                    );
+                    result.inserted_rows.push(db_row);
+                }
+                DbTransactionStep::InsertOrReplaceRow(db_row) => {
+                    let removed_db_row = self.do_insert_or_replace_row(
+                        &db_row,
+                        #[cfg(feature = "master-node")]
+                        set_last_write_moment,
+                    );
+
+                    if let Some(removed_db_row) = removed_db_row {
+                        result.removed_rows.push(removed_db_row);
+                    }
+
+                    result.inserted_rows.push(db_row);
+                }
+                DbTransactionStep::DeleteRows {
+                    partition_key,
+                    row_keys,
+                } => {
+                    let removed_rows = self.bulk_remove_rows(
+                        &partition_key,
+                        row_keys.iter(),
+                        true,
+                        #[cfg(feature = "master-node")]
+                        set_last_write_moment,
+                    );
+
+                    if let Some((removed_rows, _)) = removed_rows {
+                        result.removed_rows.extend(removed_rows);
+                    }
+                }
+                DbTransactionStep::CleanPartition(partition_key) => {
+                    let removed_partition = self.remove_partition(
+                        &partition_key,
+                        #[cfg(feature = "master-node")]
+                        set_last_write_moment,
+                    );
+
+                    if let Some(removed_partition) = removed_partition {
+                        result
+                            .removed_rows
+                            .extend(removed_partition.get_all_rows().cloned());
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate_transaction(&self, transaction: &DbTransaction) -> Result<(), DbTransactionError> {
+        // Row existence as it is going to be after the steps applied so far
+        let mut rows_state: HashMap<(&str, &str), bool> = HashMap::new();
+        let mut cleaned_partitions: HashSet<&str> = HashSet::new();
+        let mut rows_to_insert: HashSet<(&str, &str)> = HashSet::new();
+
+        for (step_no, step) in transaction.steps.iter().enumerate() {
+            #[cfg(feature = "master-node")]
+            if let DbTransactionStep::InsertRow(db_row)
+            | DbTransactionStep::InsertOrReplaceRow(db_row) = step
+            {
+                self.validate_row(db_row).map_err(|error| {
+                    DbTransactionError::SchemaValidationError { step_no, error }
+                })?;
+            }
+
+            match step {
+                DbTransactionStep::InsertRow(db_row) => {
+                    let key = (db_row.partition_key.as_str(), db_row.row_key.as_str());
+
+                    if !rows_to_insert.insert(key) {
+                        return Err(DbTransactionError::DuplicateRowToInsert {
+                            step_no,
+                            partition_key: db_row.partition_key.to_string(),
+                            row_key: db_row.row_key.to_string(),
+                        });
+                    }
+
+                    let row_exists = match rows_state.get(&key) {
+                        Some(row_exists) => *row_exists,
+                        None => {
+                            !cleaned_partitions.contains(key.0)
+                                && self
+                                    .partitions
+                                    .get(key.0)
+                                    .map(|db_partition| db_partition.get_row(key.1).is_some())
+                                    .unwrap_or(false)
+                        }
+                    };
+
+                    if row_exists {
+                        return Err(DbTransactionError::RowAlreadyExists {
+                            step_no,
+                            partition_key: db_row.partition_key.to_string(),
+                            row_key: db_row.row_key.to_string(),
+                        });
+                    }
+
+                    rows_state.insert(key, true);
+                }
+                DbTransactionStep::InsertOrReplaceRow(db_row) => {
+                    rows_state.insert(
+                        (db_row.partition_key.as_str(), db_row.row_key.as_str()),
+                        true,
+                    );
+                }
+                DbTransactionStep::DeleteRows {
+                    partition_key,
+                    row_keys,
+                } => {
+                    for row_key in row_keys {
+                        rows_state.insert((partition_key.as_str(), row_key.as_str()), false);
+                    }
+                }
+                DbTransactionStep::CleanPartition(partition_key) => {
+                    rows_state
+                        .retain(|(itm_partition_key, _), _| itm_partition_key != partition_key);
+                    cleaned_partitions.insert(partition_key.as_str());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "master-node")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::DbTable,
+        test_utils::{create_db_row, create_db_table, insert_test_row},
+    };
+
+    use super::*;
+
+    fn create_table_with_rows() -> DbTable {
+        let mut db_table = create_db_table();
+
+        insert_test_row(&mut db_table, "pk1", "rk1");
+        insert_test_row(&mut db_table, "pk1", "rk2");
+        insert_test_row(&mut db_table, "pk2", "rk1");
+
+        db_table
+    }
+
+    #[test]
+    fn test_transaction_is_applied() {
+        let mut db_table = create_table_with_rows();
+
+        let mut transaction = DbTransaction::new();
+        transaction
+            .delete_rows("pk1", vec!["rk1".to_string()])
+            .insert_row(create_db_row("pk1", "rk1"))
+            .insert_or_replace_row(create_db_row("pk1", "rk2"))
+            .clean_partition("pk2")
+            .insert_row(create_db_row("pk2", "rk1"));
+
+        let result = db_table.apply_transaction(transaction, None).unwrap();
+
+        assert_eq!(3, result.inserted_rows.len());
+        assert_eq!(3, result.removed_rows.len());
+        assert_eq!(3, db_table.get_rows_amount());
+    }
+
+    #[test]
+    fn test_transaction_is_not_applied_if_row_exists() {
+        let mut db_table = create_table_with_rows();
+
+        let mut transaction = DbTransaction::new();
+        transaction
+            .delete_rows("pk1", vec!["rk1".to_string()])
+            .insert_row(create_db_row("pk1", "rk2"));
+
+        let result = db_table.apply_transaction(transaction, None);
+
+        assert!(matches!(
+            result,
+            Err(DbTransactionError::RowAlreadyExists { step_no: 1, .. })
+        ));
+        assert_eq!(3, db_table.get_rows_amount());
+    }
+
+    #[test]
+    fn test_transaction_with_duplicate_rows_to_insert() {
+        let mut db_table = create_table_with_rows();
+
+        let mut transaction = DbTransaction::new();
+        transaction
+            .insert_row(create_db_row("pk3", "rk1"))
+            .delete_rows("pk3", vec!["rk1".to_string()])
+            .insert_row(create_db_row("pk3", "rk1"));
+
+        let result = db_table.apply_transaction(transaction, None);
+
+        assert!(matches!(
+            result,
+            Err(DbTransactionError::DuplicateRowToInsert { step_no: 2, .. })
+        ));
+        assert_eq!(2, db_table.get_partitions_amount());
+    }
+}
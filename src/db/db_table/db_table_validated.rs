@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{db::DbRow, validations::SchemaValidationError};
+
+use super::DbTable;
+
+/// Insert operations of the master node. Rows are checked against the schema before the table is changed
+impl DbTable {
+    /// Rows always match if the table has no schema. The row JSON has the TimeStamp written by the server,
+    /// so size of the JSON the client has sent is checked by DbJsonEntity::parse_with_schema
+    pub fn validate_row(&self, db_row: &DbRow) -> Result<(), SchemaValidationError> {
+        match &self.attributes.schema {
+            Some(schema) => schema.validate_fields(&db_row.data),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn insert_or_replace_row(
+        &mut self,
+        db_row: &Arc<DbRow>,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<Option<Arc<DbRow>>, SchemaValidationError> {
+        self.validate_row(db_row)?;
+        Ok(self.do_insert_or_replace_row(db_row, set_last_write_moment))
+    }
+
+    #[inline]
+    pub fn insert_row(
+        &mut self,
+        db_row: &Arc<DbRow>,
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<bool, SchemaValidationError> {
+        self.validate_row(db_row)?;
+        Ok(self.do_insert_row(db_row, set_last_write_moment))
+    }
+
+    /// Rows are inserted only if all of them match the schema. Otherwise nothing is inserted
+    #[inline]
+    pub fn bulk_insert_or_replace(
+        &mut self,
+        partition_key: &String,
+        db_rows: &[Arc<DbRow>],
+        set_last_write_moment: Option<DateTimeAsMicroseconds>,
+    ) -> Result<Option<Vec<Arc<DbRow>>>, SchemaValidationError> {
+        for db_row in db_rows {
+            self.validate_row(db_row)?;
+        }
+
+        Ok(self.do_bulk_insert_or_replace(partition_key, db_rows, set_last_write_moment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        db::{DbTable, DbTableAttributes},
+        db_json_entity::{DbEntityParseFail, DbJsonEntity, JsonTimeStamp},
+        test_utils::{create_db_row_with_fields, create_schema},
+        validations::{DbTableSchema, SchemaFieldType, SchemaValidationError},
+    };
+
+    fn create_validated_table(schema: DbTableSchema) -> DbTable {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.schema = Some(schema);
+
+        DbTable::new("test-table".to_string(), attributes)
+    }
+
+    #[test]
+    fn test_insert_is_validated() {
+        let mut db_table = create_validated_table(create_schema());
+
+        let db_row = create_db_row_with_fields("pk", "rk1", r#""Name":"test","Amount":5"#);
+        let result = db_table.insert_row(&db_row, None);
+        assert!(result.unwrap());
+
+        let db_row = create_db_row_with_fields("pk", "rk2", r#""Name":"test","Amount":"5""#);
+        let result = db_table.insert_or_replace_row(&db_row, None);
+
+        assert!(matches!(
+            result,
+            Err(SchemaValidationError::FieldTypeMismatch { field, .. }) if field == "Amount"
+        ));
+        assert_eq!(1, db_table.get_rows_amount());
+        assert_eq!(1, db_table.get_sequence());
+    }
+
+    #[test]
+    fn test_bulk_insert_is_all_or_nothing() {
+        let mut db_table = create_validated_table(create_schema());
+
+        let partition_key = "pk".to_string();
+
+        let result = db_table.bulk_insert_or_replace(
+            &partition_key,
+            &[
+                create_db_row_with_fields("pk", "rk1", r#""Name":"test","Amount":1"#),
+                create_db_row_with_fields("pk", "rk2", r#""Name":"test","Amount":null"#),
+            ],
+            None,
+        );
+
+        assert!(matches!(
+            result,
+            Err(SchemaValidationError::RequiredFieldIsNull { field, .. }) if field == "Amount"
+        ));
+        assert_eq!(0, db_table.get_rows_amount());
+        assert_eq!(0, db_table.get_partitions_amount());
+
+        db_table
+            .bulk_insert_or_replace(
+                &partition_key,
+                &[
+                    create_db_row_with_fields("pk", "rk1", r#""Name":"test","Amount":1"#),
+                    create_db_row_with_fields("pk", "rk2", r#""Name":"test","Amount":2"#),
+                ],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(2, db_table.get_rows_amount());
+    }
+
+    #[test]
+    fn test_client_json_is_validated_at_parse() {
+        let json =
+            r#"{"PartitionKey":"pk","RowKey":"rk","TimeStamp":null,"Name":"test","Amount":"5"}"#;
+
+        let mut schema = create_schema();
+        schema.max_row_size = Some(json.len());
+
+        let result = DbJsonEntity::parse_with_schema(json.as_bytes(), &schema);
+
+        assert!(matches!(
+            result,
+            Err(DbEntityParseFail::SchemaValidationError(
+                SchemaValidationError::FieldTypeMismatch { field, expected: SchemaFieldType::Number, position }
+            )) if field == "Amount" && position == json.find("\"5\"").unwrap()
+        ));
+
+        let json =
+            r#"{"PartitionKey":"pk","RowKey":"rk","TimeStamp":null,"Name":"test","Amount":5}"#;
+        let db_json_entity = DbJsonEntity::parse_with_schema(json.as_bytes(), &schema).unwrap();
+
+        // TimeStamp of the server makes the row bigger than the JSON the client has sent
+        let db_row = Arc::new(db_json_entity.new_db_row(&JsonTimeStamp::now()));
+        assert!(db_row.data.len() > json.len());
+
+        let mut db_table = create_validated_table(schema);
+        assert!(db_table.insert_row(&db_row, None).unwrap());
+    }
+}
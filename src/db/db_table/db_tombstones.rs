@@ -0,0 +1,372 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use rust_extensions::{date_time::DateTimeAsMicroseconds, lazy::LazyVec};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbTombstoneRange {
+    /// Table was cleared
+    Table,
+    /// Partition was removed with all its rows
+    Partition(String),
+    Row {
+        partition_key: String,
+        row_key: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct DbTombstone {
+    pub range: DbTombstoneRange,
+    pub deleted: DateTimeAsMicroseconds,
+    pub sequence: u64,
+}
+
+#[derive(Default)]
+pub struct DbPartitionTombstones {
+    // Range tombstone of the removed partition. Row tombstones written before it are dropped
+    removed: Option<DbTombstone>,
+    rows: BTreeMap<String, DbTombstone>,
+}
+
+impl DbPartitionTombstones {
+    pub fn get_removed(&self) -> Option<&DbTombstone> {
+        self.removed.as_ref()
+    }
+
+    pub fn get_row(&self, row_key: &str) -> Option<&DbTombstone> {
+        self.rows.get(row_key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len() + self.removed.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_none() && self.rows.is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &DbTombstone> {
+        self.removed.iter().chain(self.rows.values())
+    }
+
+    fn purge(&mut self, deleted_before: DateTimeAsMicroseconds) {
+        if let Some(removed) = &self.removed {
+            if removed.deleted.unix_microseconds < deleted_before.unix_microseconds {
+                self.removed = None;
+            }
+        }
+
+        self.rows
+            .retain(|_, itm| itm.deleted.unix_microseconds >= deleted_before.unix_microseconds);
+    }
+}
+
+#[derive(Default)]
+pub struct DbTombstones {
+    // Range tombstone of the cleared table. Tombstones written before it are dropped
+    cleared: Option<DbTombstone>,
+    partitions: BTreeMap<String, DbPartitionTombstones>,
+}
+
+impl DbTombstones {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cleared.iter().count() + self.partitions.values().map(|itm| itm.len()).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cleared.is_none() && self.partitions.is_empty()
+    }
+
+    pub fn add(&mut self, tombstone: DbTombstone) {
+        match &tombstone.range {
+            DbTombstoneRange::Table => {
+                self.partitions.clear();
+                self.cleared = Some(tombstone);
+            }
+            DbTombstoneRange::Partition(partition_key) => {
+                let partition = self.get_partition_mut(partition_key);
+                partition.rows.clear();
+                partition.removed = Some(tombstone);
+            }
+            DbTombstoneRange::Row {
+                partition_key,
+                row_key,
+            } => {
+                let row_key = row_key.to_string();
+                self.get_partition_mut(partition_key)
+                    .rows
+                    .insert(row_key, tombstone);
+            }
+        }
+    }
+
+    fn get_partition_mut(&mut self, partition_key: &str) -> &mut DbPartitionTombstones {
+        if !self.partitions.contains_key(partition_key) {
+            self.partitions
+                .insert(partition_key.to_string(), DbPartitionTombstones::default());
+        }
+
+        self.partitions.get_mut(partition_key).unwrap()
+    }
+
+    /// Removes the tombstone of the row which is written again.
+    /// Range tombstones stay - the row has a higher sequence than them
+    pub fn remove_row(&mut self, partition_key: &str, row_key: &str) -> Option<DbTombstone> {
+        let partition = self.partitions.get_mut(partition_key)?;
+
+        let result = partition.rows.remove(row_key)?;
+
+        if partition.is_empty() {
+            self.partitions.remove(partition_key);
+        }
+
+        Some(result)
+    }
+
+    pub fn remove(&mut self, range: &DbTombstoneRange) -> Option<DbTombstone> {
+        match range {
+            DbTombstoneRange::Table => self.cleared.take(),
+            DbTombstoneRange::Partition(partition_key) => {
+                let partition = self.partitions.get_mut(partition_key)?;
+                let result = partition.removed.take()?;
+
+                if partition.is_empty() {
+                    self.partitions.remove(partition_key);
+                }
+
+                Some(result)
+            }
+            DbTombstoneRange::Row {
+                partition_key,
+                row_key,
+            } => self.remove_row(partition_key, row_key),
+        }
+    }
+
+    /// Removes the tombstone only if it is the one with the given sequence.
+    /// Tombstone written again for the same range after GC has found the old one stays
+    pub fn remove_with_sequence(
+        &mut self,
+        range: &DbTombstoneRange,
+        sequence: u64,
+    ) -> Option<DbTombstone> {
+        if self.get_by_range(range)?.sequence != sequence {
+            return None;
+        }
+
+        self.remove(range)
+    }
+
+    pub fn get_by_range(&self, range: &DbTombstoneRange) -> Option<&DbTombstone> {
+        match range {
+            DbTombstoneRange::Table => self.cleared.as_ref(),
+            DbTombstoneRange::Partition(partition_key) => {
+                self.partitions.get(partition_key)?.get_removed()
+            }
+            DbTombstoneRange::Row {
+                partition_key,
+                row_key,
+            } => self.get(partition_key, row_key),
+        }
+    }
+
+    pub fn get_cleared(&self) -> Option<&DbTombstone> {
+        self.cleared.as_ref()
+    }
+
+    pub fn get_partition(&self, partition_key: &str) -> Option<&DbPartitionTombstones> {
+        self.partitions.get(partition_key)
+    }
+
+    pub fn get(&self, partition_key: &str, row_key: &str) -> Option<&DbTombstone> {
+        self.partitions.get(partition_key)?.get_row(row_key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DbTombstone> {
+        self.cleared
+            .iter()
+            .chain(self.partitions.values().flat_map(|itm| itm.iter()))
+    }
+
+    /// Tombstones written after the change with the given sequence, ordered by sequence
+    pub fn get_since(&self, sequence: u64) -> Option<Vec<&DbTombstone>> {
+        let mut result = LazyVec::new();
+
+        for tombstone in self.iter() {
+            if tombstone.sequence > sequence {
+                result.add(tombstone);
+            }
+        }
+
+        let mut result = result.get_result()?;
+        result.sort_by_key(|itm| itm.sequence);
+        Some(result)
+    }
+
+    pub fn get_to_gc(
+        &self,
+        now: DateTimeAsMicroseconds,
+        retention: Duration,
+    ) -> Option<Vec<&DbTombstone>> {
+        let deleted_before = get_deleted_before(now, retention);
+
+        let mut result = LazyVec::new();
+
+        for tombstone in self.iter() {
+            if tombstone.deleted.unix_microseconds < deleted_before.unix_microseconds {
+                result.add(tombstone);
+            }
+        }
+
+        result.get_result()
+    }
+
+    /// Removes tombstones deleted before the moment. Returns amount of purged tombstones
+    pub fn purge(&mut self, deleted_before: DateTimeAsMicroseconds) -> usize {
+        let amount_before = self.len();
+
+        if let Some(cleared) = &self.cleared {
+            if cleared.deleted.unix_microseconds < deleted_before.unix_microseconds {
+                self.cleared = None;
+            }
+        }
+
+        for partition in self.partitions.values_mut() {
+            partition.purge(deleted_before);
+        }
+
+        self.partitions.retain(|_, itm| !itm.is_empty());
+
+        amount_before - self.len()
+    }
+}
+
+pub fn get_deleted_before(
+    now: DateTimeAsMicroseconds,
+    retention: Duration,
+) -> DateTimeAsMicroseconds {
+    DateTimeAsMicroseconds::new(now.unix_microseconds - retention.as_micros() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use super::*;
+
+    fn create_tombstone(
+        partition_key: &str,
+        row_key: &str,
+        deleted: i64,
+        sequence: u64,
+    ) -> DbTombstone {
+        DbTombstone {
+            range: DbTombstoneRange::Row {
+                partition_key: partition_key.to_string(),
+                row_key: row_key.to_string(),
+            },
+            deleted: DateTimeAsMicroseconds::new(deleted),
+            sequence,
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut tombstones = DbTombstones::new();
+
+        tombstones.add(create_tombstone("pk1", "rk1", 1_000_000, 1));
+        tombstones.add(create_tombstone("pk1", "rk1", 2_000_000, 2));
+        tombstones.add(create_tombstone("pk2", "rk1", 3_000_000, 3));
+
+        assert_eq!(2, tombstones.len());
+        assert_eq!(2, tombstones.get("pk1", "rk1").unwrap().sequence);
+
+        let result = tombstones.get_since(2).unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!(
+            DbTombstoneRange::Row {
+                partition_key: "pk2".to_string(),
+                row_key: "rk1".to_string()
+            },
+            result[0].range
+        );
+
+        assert!(tombstones.remove_row("pk1", "rk1").is_some());
+        assert!(tombstones.get_partition("pk1").is_none());
+        assert_eq!(1, tombstones.len());
+    }
+
+    #[test]
+    fn test_range_tombstones() {
+        let mut tombstones = DbTombstones::new();
+
+        tombstones.add(create_tombstone("pk1", "rk1", 1_000_000, 1));
+        tombstones.add(create_tombstone("pk1", "rk2", 1_000_000, 2));
+        tombstones.add(create_tombstone("pk2", "rk1", 1_000_000, 3));
+
+        tombstones.add(DbTombstone {
+            range: DbTombstoneRange::Partition("pk1".to_string()),
+            deleted: DateTimeAsMicroseconds::new(2_000_000),
+            sequence: 4,
+        });
+
+        assert_eq!(2, tombstones.len());
+        assert!(tombstones.get("pk1", "rk1").is_none());
+        assert_eq!(
+            4,
+            tombstones
+                .get_partition("pk1")
+                .unwrap()
+                .get_removed()
+                .unwrap()
+                .sequence
+        );
+
+        tombstones.add(create_tombstone("pk1", "rk3", 3_000_000, 5));
+
+        let result: Vec<u64> = tombstones
+            .get_since(2)
+            .unwrap()
+            .iter()
+            .map(|itm| itm.sequence)
+            .collect();
+        assert_eq!(vec![3, 4, 5], result);
+
+        tombstones.add(DbTombstone {
+            range: DbTombstoneRange::Table,
+            deleted: DateTimeAsMicroseconds::new(4_000_000),
+            sequence: 6,
+        });
+
+        assert_eq!(1, tombstones.len());
+        assert_eq!(6, tombstones.get_cleared().unwrap().sequence);
+        assert!(tombstones.get_partition("pk1").is_none());
+
+        assert!(tombstones.remove(&DbTombstoneRange::Table).is_some());
+        assert!(tombstones.is_empty());
+    }
+
+    #[test]
+    fn test_gc_and_purge() {
+        let mut tombstones = DbTombstones::new();
+
+        tombstones.add(create_tombstone("pk1", "rk1", 1_000_000, 1));
+        tombstones.add(create_tombstone("pk1", "rk2", 5_000_000, 2));
+        tombstones.add(create_tombstone("pk2", "rk1", 2_000_000, 3));
+
+        let now = DateTimeAsMicroseconds::new(7_000_000);
+
+        let result = tombstones.get_to_gc(now, Duration::from_secs(4)).unwrap();
+        assert_eq!(2, result.len());
+
+        let purged = tombstones.purge(get_deleted_before(now, Duration::from_secs(4)));
+        assert_eq!(2, purged);
+        assert_eq!(1, tombstones.len());
+        assert!(tombstones.get("pk1", "rk2").is_some());
+        assert!(tombstones.get_partition("pk2").is_none());
+    }
+}
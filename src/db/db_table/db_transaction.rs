@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::db::DbRow;
+#[cfg(feature = "master-node")]
+use crate::validations::SchemaValidationError;
+
+pub enum DbTransactionStep {
+    InsertRow(Arc<DbRow>),
+    InsertOrReplaceRow(Arc<DbRow>),
+    DeleteRows {
+        partition_key: String,
+        row_keys: Vec<String>,
+    },
+    CleanPartition(String),
+}
+
+#[derive(Default)]
+pub struct DbTransaction {
+    pub steps: Vec<DbTransactionStep>,
+}
+
+impl DbTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_row(&mut self, db_row: Arc<DbRow>) -> &mut Self {
+        self.steps.push(DbTransactionStep::InsertRow(db_row));
+        self
+    }
+
+    pub fn insert_or_replace_row(&mut self, db_row: Arc<DbRow>) -> &mut Self {
+        self.steps
+            .push(DbTransactionStep::InsertOrReplaceRow(db_row));
+        self
+    }
+
+    pub fn delete_rows(&mut self, partition_key: &str, row_keys: Vec<String>) -> &mut Self {
+        self.steps.push(DbTransactionStep::DeleteRows {
+            partition_key: partition_key.to_string(),
+            row_keys,
+        });
+        self
+    }
+
+    pub fn clean_partition(&mut self, partition_key: &str) -> &mut Self {
+        self.steps
+            .push(DbTransactionStep::CleanPartition(partition_key.to_string()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub struct DbTransactionResult {
+    pub inserted_rows: Vec<Arc<DbRow>>,
+    /// Rows which were deleted or replaced during the transaction
+    pub removed_rows: Vec<Arc<DbRow>>,
+}
+
+impl DbTransactionResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug)]
+pub enum DbTransactionError {
+    RowAlreadyExists {
+        step_no: usize,
+        partition_key: String,
+        row_key: String,
+    },
+    DuplicateRowToInsert {
+        step_no: usize,
+        partition_key: String,
+        row_key: String,
+    },
+    #[cfg(feature = "master-node")]
+    SchemaValidationError {
+        step_no: usize,
+        error: SchemaValidationError,
+    },
+}
@@ -5,15 +5,41 @@ mod db_table_attributes;
 #[cfg(feature = "master-node")]
 mod db_table_master_node;
 #[cfg(feature = "master-node")]
-pub use db_table_attributes::DbTableAttributes;
+pub use db_table_attributes::{DbTableAttributes, SlidingExpiration};
 
 pub use db_table::DbTable;
 #[cfg(feature = "master-node")]
 pub use db_table_master_node::*;
 #[cfg(feature = "master-node")]
+mod conditional_operation_error;
+#[cfg(feature = "master-node")]
+pub use conditional_operation_error::*;
+#[cfg(feature = "master-node")]
 mod data_to_gc;
 #[cfg(feature = "master-node")]
+mod db_table_conditional;
+#[cfg(feature = "master-node")]
+mod db_table_validated;
+#[cfg(feature = "master-node")]
 pub use data_to_gc::*;
+#[cfg(feature = "master-node")]
+mod db_table_gc_report;
+#[cfg(feature = "master-node")]
+pub use db_table_gc_report::*;
+#[cfg(feature = "master-node")]
+mod db_tombstones;
+#[cfg(feature = "master-node")]
+pub use db_tombstones::*;
 
 mod db_partitions_container;
 pub use db_partitions_container::*;
+
+mod continuation_token;
+pub use continuation_token::*;
+
+mod db_transaction;
+pub use db_transaction::*;
+mod db_table_transaction;
+
+mod db_table_change_event;
+pub use db_table_change_event::*;
@@ -1,7 +1,13 @@
-pub use db_table::DbTable;
+pub use db_table::{
+    ContinuationToken, ContinuationTokenError, DbTable, DbTableChangeEvent, DbTableChangeSink,
+    DbTransaction, DbTransactionError, DbTransactionResult, DbTransactionStep, DirtyPartitionState,
+};
 
 #[cfg(feature = "master-node")]
-pub use db_table::{DataToGc, DbTableAttributes};
+pub use db_table::{
+    ConditionalOperationError, DataToGc, DbPartitionTombstones, DbTableAttributes, DbTableGcReport,
+    DbTombstone, DbTombstoneRange, DbTombstones, SlidingExpiration,
+};
 
 pub use db_partition::*;
 
@@ -0,0 +1,7 @@
+#[derive(Debug)]
+pub enum FilterParseError {
+    UnexpectedEndOfExpression,
+    UnexpectedToken { token: String, position: usize },
+    InvalidNumber { value: String, position: usize },
+    UnterminatedString { position: usize },
+}
@@ -0,0 +1,96 @@
+use std::cmp::Ordering;
+
+use crate::db_json_entity::JsonFieldValue;
+
+use super::{FilterOperator, FilterValue};
+
+pub fn compare(
+    raw: &[u8],
+    field_path: &[String],
+    operator: FilterOperator,
+    value: &FilterValue,
+) -> bool {
+    // Missing field is treated the same way as null one
+    let field_value = match crate::db_json_entity::find_json_field(raw, field_path) {
+        Some(field_value) => JsonFieldValue::from_raw(field_value),
+        None => Some(JsonFieldValue::Null),
+    };
+
+    let ordering = match field_value {
+        Some(field_value) => get_ordering(&field_value, value),
+        None => None,
+    };
+
+    match ordering {
+        Some(ordering) => match operator {
+            FilterOperator::Eq => ordering == Ordering::Equal,
+            FilterOperator::Ne => ordering != Ordering::Equal,
+            FilterOperator::Gt => ordering == Ordering::Greater,
+            FilterOperator::Ge => ordering != Ordering::Less,
+            FilterOperator::Lt => ordering == Ordering::Less,
+            FilterOperator::Le => ordering != Ordering::Greater,
+        },
+        // Values of different types are never equal and can not be ordered
+        None => operator == FilterOperator::Ne,
+    }
+}
+
+fn get_ordering(field_value: &JsonFieldValue, value: &FilterValue) -> Option<Ordering> {
+    match (field_value, value) {
+        (JsonFieldValue::String(_), FilterValue::String(value)) => {
+            let field_value = field_value.as_string()?;
+            Some(field_value.as_ref().cmp(value.as_str()))
+        }
+        (JsonFieldValue::Number(field_value), FilterValue::Number(value)) => {
+            field_value.partial_cmp(value)
+        }
+        (JsonFieldValue::Bool(field_value), FilterValue::Bool(value)) => {
+            Some(field_value.cmp(value))
+        }
+        (JsonFieldValue::Null, FilterValue::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db_filter::FilterExpression;
+
+    const TEST_JSON: &str = r#"{"PartitionKey":"pk","RowKey":"rk","Status":"Active","Amount":150.5,"IsVip":false,"Address":{"City":"Paris"},"Comment":null}"#;
+
+    fn matches(filter: &str) -> bool {
+        FilterExpression::parse(filter)
+            .unwrap()
+            .matches_json(TEST_JSON.as_bytes())
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(matches("Status eq 'Active'"));
+        assert!(matches("Status ne 'Blocked'"));
+        assert!(matches("Amount gt 100"));
+        assert!(matches("Amount le 150.5"));
+        assert!(!matches("Amount lt 150.5"));
+        assert!(matches("IsVip eq false"));
+        assert!(matches("Address.City eq 'Paris'"));
+        assert!(matches("Status gt 'Aardvark'"));
+    }
+
+    #[test]
+    fn test_nulls_and_type_mismatches() {
+        assert!(matches("Comment eq null"));
+        assert!(matches("Unknown eq null"));
+        assert!(matches("Address.Unknown eq null"));
+        assert!(!matches("Amount eq '150.5'"));
+        assert!(matches("Amount ne '150.5'"));
+        assert!(!matches("Status gt 1"));
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert!(matches("Status eq 'Active' and Amount gt 100"));
+        assert!(!matches("Status eq 'Active' and Amount gt 200"));
+        assert!(matches("Status eq 'Blocked' or Amount gt 100"));
+        assert!(matches("not (Status eq 'Blocked' or IsVip eq true)"));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::db::DbRow;
+
+use super::FilterParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpression {
+    Compare {
+        field_path: Vec<String>,
+        operator: FilterOperator,
+        value: FilterValue,
+    },
+    And(Box<FilterExpression>, Box<FilterExpression>),
+    Or(Box<FilterExpression>, Box<FilterExpression>),
+    Not(Box<FilterExpression>),
+}
+
+impl FilterExpression {
+    /// Parses expressions like: Status eq 'Active' and (Amount gt 100 or not Address.City eq null)
+    pub fn parse(src: &str) -> Result<Self, FilterParseError> {
+        super::filter_parser::parse(src)
+    }
+
+    pub fn matches(&self, db_row: &DbRow) -> bool {
+        self.matches_json(db_row.data.as_slice())
+    }
+
+    pub fn matches_json(&self, raw: &[u8]) -> bool {
+        match self {
+            Self::Compare {
+                field_path,
+                operator,
+                value,
+            } => super::filter_evaluator::compare(raw, field_path, *operator, value),
+            Self::And(left, right) => left.matches_json(raw) && right.matches_json(raw),
+            Self::Or(left, right) => left.matches_json(raw) || right.matches_json(raw),
+            Self::Not(expression) => !expression.matches_json(raw),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use super::{
+    filter_tokenizer::{FilterToken, FilterTokenWithPosition},
+    FilterExpression, FilterOperator, FilterParseError, FilterValue,
+};
+
+// Grammar. Operator precedence is: not, and, or
+//
+// or_expression  := and_expression ('or' and_expression)*
+// and_expression := unary ('and' unary)*
+// unary          := 'not' unary | '(' or_expression ')' | comparison
+// comparison     := field_path operator value
+
+pub fn parse(src: &str) -> Result<FilterExpression, FilterParseError> {
+    let tokens = super::filter_tokenizer::tokenize(src)?;
+
+    let mut parser = FilterParser { tokens, pos: 0 };
+
+    let result = parser.parse_or()?;
+
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(unexpected_token(token));
+    }
+
+    Ok(result)
+}
+
+struct FilterParser {
+    tokens: Vec<FilterTokenWithPosition>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn next(&mut self) -> Result<&FilterTokenWithPosition, FilterParseError> {
+        let result = self
+            .tokens
+            .get(self.pos)
+            .ok_or(FilterParseError::UnexpectedEndOfExpression)?;
+
+        self.pos += 1;
+
+        Ok(result)
+    }
+
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        match self.tokens.get(self.pos) {
+            Some(FilterTokenWithPosition {
+                token: FilterToken::Identifier(value),
+                ..
+            }) => value.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpression, FilterParseError> {
+        let mut result = self.parse_and()?;
+
+        while self.next_is_keyword("or") {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            result = FilterExpression::Or(Box::new(result), Box::new(right));
+        }
+
+        Ok(result)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpression, FilterParseError> {
+        let mut result = self.parse_unary()?;
+
+        while self.next_is_keyword("and") {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            result = FilterExpression::And(Box::new(result), Box::new(right));
+        }
+
+        Ok(result)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpression, FilterParseError> {
+        if self.next_is_keyword("not") {
+            self.pos += 1;
+            let expression = self.parse_unary()?;
+            return Ok(FilterExpression::Not(Box::new(expression)));
+        }
+
+        let token = self.next()?;
+
+        match &token.token {
+            FilterToken::OpenParenthesis => {
+                let result = self.parse_or()?;
+
+                let token = self.next()?;
+                if token.token != FilterToken::CloseParenthesis {
+                    return Err(unexpected_token(token));
+                }
+
+                Ok(result)
+            }
+            FilterToken::Identifier(field_path) => {
+                let field_path = field_path.split('.').map(|itm| itm.to_string()).collect();
+                self.parse_comparison(field_path)
+            }
+            _ => Err(unexpected_token(token)),
+        }
+    }
+
+    fn parse_comparison(
+        &mut self,
+        field_path: Vec<String>,
+    ) -> Result<FilterExpression, FilterParseError> {
+        let token = self.next()?;
+
+        let operator = match &token.token {
+            FilterToken::Identifier(value) => match value.to_lowercase().as_str() {
+                "eq" => FilterOperator::Eq,
+                "ne" => FilterOperator::Ne,
+                "gt" => FilterOperator::Gt,
+                "ge" => FilterOperator::Ge,
+                "lt" => FilterOperator::Lt,
+                "le" => FilterOperator::Le,
+                _ => return Err(unexpected_token(token)),
+            },
+            _ => return Err(unexpected_token(token)),
+        };
+
+        let token = self.next()?;
+
+        let value = match &token.token {
+            FilterToken::String(value) => FilterValue::String(value.to_string()),
+            FilterToken::Number(value) => FilterValue::Number(*value),
+            FilterToken::Identifier(value) => match value.to_lowercase().as_str() {
+                "true" => FilterValue::Bool(true),
+                "false" => FilterValue::Bool(false),
+                "null" => FilterValue::Null,
+                _ => return Err(unexpected_token(token)),
+            },
+            _ => return Err(unexpected_token(token)),
+        };
+
+        Ok(FilterExpression::Compare {
+            field_path,
+            operator,
+            value,
+        })
+    }
+}
+
+fn unexpected_token(token: &FilterTokenWithPosition) -> FilterParseError {
+    let value = match &token.token {
+        FilterToken::Identifier(value) => value.to_string(),
+        FilterToken::String(value) => format!("'{}'", value),
+        FilterToken::Number(value) => value.to_string(),
+        FilterToken::OpenParenthesis => "(".to_string(),
+        FilterToken::CloseParenthesis => ")".to_string(),
+    };
+
+    FilterParseError::UnexpectedToken {
+        token: value,
+        position: token.position,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db_filter::{FilterExpression, FilterOperator, FilterParseError, FilterValue};
+
+    #[test]
+    fn test_parse_precedence() {
+        let result = FilterExpression::parse("A eq 1 or B eq 'x' and not C eq true").unwrap();
+
+        let expected = FilterExpression::Or(
+            Box::new(FilterExpression::Compare {
+                field_path: vec!["A".to_string()],
+                operator: FilterOperator::Eq,
+                value: FilterValue::Number(1.0),
+            }),
+            Box::new(FilterExpression::And(
+                Box::new(FilterExpression::Compare {
+                    field_path: vec!["B".to_string()],
+                    operator: FilterOperator::Eq,
+                    value: FilterValue::String("x".to_string()),
+                }),
+                Box::new(FilterExpression::Not(Box::new(FilterExpression::Compare {
+                    field_path: vec!["C".to_string()],
+                    operator: FilterOperator::Eq,
+                    value: FilterValue::Bool(true),
+                }))),
+            )),
+        );
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            FilterExpression::parse("A eq"),
+            Err(FilterParseError::UnexpectedEndOfExpression)
+        ));
+
+        assert!(matches!(
+            FilterExpression::parse("A eq 'abc"),
+            Err(FilterParseError::UnterminatedString { position: 5 })
+        ));
+
+        assert!(matches!(
+            FilterExpression::parse("A like 'abc'"),
+            Err(FilterParseError::UnexpectedToken { position: 2, .. })
+        ));
+
+        assert!(matches!(
+            FilterExpression::parse("(A eq 1"),
+            Err(FilterParseError::UnexpectedEndOfExpression)
+        ));
+    }
+}
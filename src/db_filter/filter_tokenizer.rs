@@ -0,0 +1,128 @@
+use super::FilterParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterToken {
+    Identifier(String),
+    String(String),
+    Number(f64),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+pub struct FilterTokenWithPosition {
+    pub token: FilterToken,
+    pub position: usize,
+}
+
+pub fn tokenize(src: &str) -> Result<Vec<FilterTokenWithPosition>, FilterParseError> {
+    let mut result = Vec::new();
+
+    let chars: Vec<(usize, char)> = src.char_indices().collect();
+
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (position, c) = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '(' || c == ')' {
+            let token = if c == '(' {
+                FilterToken::OpenParenthesis
+            } else {
+                FilterToken::CloseParenthesis
+            };
+
+            result.push(FilterTokenWithPosition { token, position });
+            i += 1;
+            continue;
+        }
+
+        if c == '\'' {
+            // Quote inside of the string is escaped by doubling it: 'O''Brien'
+            let mut value = String::new();
+            i += 1;
+
+            loop {
+                if i >= chars.len() {
+                    return Err(FilterParseError::UnterminatedString { position });
+                }
+
+                let (_, c) = chars[i];
+
+                if c == '\'' {
+                    if i + 1 < chars.len() && chars[i + 1].1 == '\'' {
+                        value.push('\'');
+                        i += 2;
+                        continue;
+                    }
+
+                    i += 1;
+                    break;
+                }
+
+                value.push(c);
+                i += 1;
+            }
+
+            result.push(FilterTokenWithPosition {
+                token: FilterToken::String(value),
+                position,
+            });
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '-' {
+            let mut value = String::new();
+
+            while i < chars.len() && is_number_symbol(chars[i].1) {
+                value.push(chars[i].1);
+                i += 1;
+            }
+
+            let number = match value.parse() {
+                Ok(number) => number,
+                Err(_) => return Err(FilterParseError::InvalidNumber { value, position }),
+            };
+
+            result.push(FilterTokenWithPosition {
+                token: FilterToken::Number(number),
+                position,
+            });
+            continue;
+        }
+
+        if is_identifier_symbol(c) {
+            let mut value = String::new();
+
+            while i < chars.len() && is_identifier_symbol(chars[i].1) {
+                value.push(chars[i].1);
+                i += 1;
+            }
+
+            result.push(FilterTokenWithPosition {
+                token: FilterToken::Identifier(value),
+                position,
+            });
+            continue;
+        }
+
+        return Err(FilterParseError::UnexpectedToken {
+            token: c.to_string(),
+            position,
+        });
+    }
+
+    Ok(result)
+}
+
+fn is_number_symbol(c: char) -> bool {
+    c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E'
+}
+
+fn is_identifier_symbol(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
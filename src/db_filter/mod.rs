@@ -0,0 +1,8 @@
+mod error;
+mod filter_evaluator;
+mod filter_expression;
+mod filter_parser;
+mod filter_tokenizer;
+
+pub use error::FilterParseError;
+pub use filter_expression::*;
@@ -1,10 +1,12 @@
 use crate::db::DbRow;
+use crate::validations::{DbKeysValidator, DbTableSchema, KeyValidationError, KeyValidator};
 
 use my_json::json_reader::array_parser::ArrayToJsonObjectsSplitter;
 
 use std::{collections::BTreeMap, sync::Arc};
 
 use super::DbEntityParseFail;
+use super::JsonFieldValue;
 use super::JsonKeyValuePosition;
 use super::JsonTimeStamp;
 use my_json::json_reader::JsonFirstLineReader;
@@ -105,6 +107,36 @@ impl<'s> DbJsonEntity<'s> {
         return Ok(result);
     }
 
+    pub fn parse_and_validate(
+        raw: &'s [u8],
+        keys_validator: &DbKeysValidator,
+    ) -> Result<Self, DbEntityParseFail> {
+        let result = Self::parse(raw)?;
+
+        validate_key(&keys_validator.partition_key, result.partition_key)
+            .map_err(DbEntityParseFail::InvalidPartitionKey)?;
+
+        validate_key(&keys_validator.row_key, result.row_key)
+            .map_err(DbEntityParseFail::InvalidRowKey)?;
+
+        Ok(result)
+    }
+
+    /// Checks the JSON the client has sent, so size and positions of the errors are the client ones.
+    /// Rows have to be validated here - once the row is built, its JSON has the TimeStamp of the server
+    pub fn parse_with_schema(
+        raw: &'s [u8],
+        schema: &DbTableSchema,
+    ) -> Result<Self, DbEntityParseFail> {
+        let result = Self::parse(raw)?;
+
+        schema
+            .validate(raw)
+            .map_err(DbEntityParseFail::SchemaValidationError)?;
+
+        Ok(result)
+    }
+
     pub fn new_db_row(&self, inject_time_stamp: &JsonTimeStamp) -> DbRow {
         let data =
             compile_row_content(self.raw, &self.timestamp_value_position, &inject_time_stamp);
@@ -208,6 +240,15 @@ impl<'s> DbJsonEntity<'s> {
     }
 }
 
+// Keys are kept escaped as they are in the JSON, so they are decoded to validate the real symbols
+fn validate_key(key_validator: &KeyValidator, key: &str) -> Result<(), KeyValidationError> {
+    let key = JsonFieldValue::String(key.as_bytes())
+        .as_string()
+        .ok_or(KeyValidationError::InvalidEscapeSequence)?;
+
+    key_validator.validate(key.as_ref())
+}
+
 fn compile_row_content(
     raw: &[u8],
     time_stamp_value_position: &Option<JsonKeyValuePosition>,
@@ -227,7 +268,13 @@ fn compile_row_content(
 #[cfg(test)]
 mod tests {
 
-    use crate::db_json_entity::{DbEntityParseFail, JsonTimeStamp};
+    use crate::{
+        db_json_entity::{DbEntityParseFail, JsonTimeStamp},
+        validations::{
+            DbKeysValidator, DbTableSchema, KeyValidationError, SchemaFieldType,
+            SchemaValidationError,
+        },
+    };
 
     use super::DbJsonEntity;
 
@@ -278,6 +325,116 @@ mod tests {
             panic!("Should not be here")
         }
     }
+
+    #[test]
+    pub fn parse_and_validate_keys() {
+        let mut keys_validator = DbKeysValidator::create_default();
+        keys_validator.partition_key.forbidden_symbols = vec!['/'];
+
+        let result = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "pk", "RowKey": ""}"#.as_bytes(),
+            &keys_validator,
+        );
+
+        if let Err(DbEntityParseFail::InvalidRowKey(KeyValidationError::KeyIsEmpty)) = result {
+        } else {
+            panic!("Should not be here")
+        }
+
+        let result = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "p/k", "RowKey": "rk"}"#.as_bytes(),
+            &keys_validator,
+        );
+
+        if let Err(DbEntityParseFail::InvalidPartitionKey(KeyValidationError::ForbiddenSymbol {
+            symbol: '/',
+            ..
+        })) = result
+        {
+        } else {
+            panic!("Should not be here")
+        }
+
+        let entity = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "pk", "RowKey": "rk"}"#.as_bytes(),
+            &keys_validator,
+        )
+        .unwrap();
+
+        assert_eq!("pk", entity.partition_key);
+        assert_eq!("rk", entity.row_key);
+    }
+
+    #[test]
+    pub fn parse_and_validate_escaped_keys() {
+        let mut keys_validator = DbKeysValidator::create_default();
+        keys_validator.row_key.max_length = 3;
+
+        let result = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "p\nk", "RowKey": "rk"}"#.as_bytes(),
+            &keys_validator,
+        );
+
+        if let Err(DbEntityParseFail::InvalidPartitionKey(KeyValidationError::ForbiddenSymbol {
+            symbol: '\n',
+            position: 1,
+        })) = result
+        {
+        } else {
+            panic!("Should not be here")
+        }
+
+        let result = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "pk", "RowKey": "r\u0000"}"#.as_bytes(),
+            &keys_validator,
+        );
+
+        if let Err(DbEntityParseFail::InvalidRowKey(KeyValidationError::ForbiddenSymbol {
+            symbol: '\0',
+            ..
+        })) = result
+        {
+        } else {
+            panic!("Should not be here")
+        }
+
+        // Length is checked against the decoded key
+        let entity = DbJsonEntity::parse_and_validate(
+            r#"{"PartitionKey": "pk", "RowKey": "\u0041bc"}"#.as_bytes(),
+            &keys_validator,
+        )
+        .unwrap();
+
+        assert_eq!("\\u0041bc", entity.row_key);
+    }
+
+    #[test]
+    pub fn parse_with_schema() {
+        let mut schema = DbTableSchema::new();
+        schema.add_field("Amount", SchemaFieldType::Number, true);
+
+        let result = DbJsonEntity::parse_with_schema(
+            r#"{"PartitionKey": "pk", "RowKey": "rk"}"#.as_bytes(),
+            &schema,
+        );
+
+        if let Err(DbEntityParseFail::SchemaValidationError(
+            SchemaValidationError::RequiredFieldIsMissing { field },
+        )) = result
+        {
+            assert_eq!("Amount", field);
+        } else {
+            panic!("Should not be here")
+        }
+
+        let result = DbJsonEntity::parse_with_schema(
+            r#"{"PartitionKey": "pk", "RowKey": "rk", "Amount": 5}"#.as_bytes(),
+            &schema,
+        );
+
+        assert!(result.is_ok());
+    }
+
     #[test]
     pub fn parse_some_case_from_real_life() {
         let src_json = r#"{"value":{"is_enabled":true,"fee_percent":5.0,"min_balance_usd":100.0,"fee_period_days":30,"inactivity_period_days":90},"PartitionKey":"*","RowKey":"*"}"#;
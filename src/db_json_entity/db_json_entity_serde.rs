@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+use super::DbJsonEntity;
+
+impl<'s> DbJsonEntity<'s> {
+    pub fn deserialize<T: Deserialize<'s>>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(self.raw)
+    }
+}
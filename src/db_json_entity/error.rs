@@ -1,5 +1,7 @@
 use my_json::json_reader::JsonParseError;
 
+use crate::validations::{KeyValidationError, SchemaValidationError};
+
 #[derive(Debug)]
 pub enum DbEntityParseFail {
     FieldPartitionKeyIsRequired,
@@ -8,6 +10,11 @@ pub enum DbEntityParseFail {
     FieldRowKeyCanNotBeNull,
     JsonParseError(JsonParseError),
     PartitionKeyIsTooLong,
+    InvalidPartitionKey(KeyValidationError),
+    InvalidRowKey(KeyValidationError),
+    SchemaValidationError(SchemaValidationError),
+    #[cfg(feature = "serde")]
+    SerdeJsonError(serde_json::Error),
 }
 
 impl From<JsonParseError> for DbEntityParseFail {
@@ -15,3 +22,10 @@ impl From<JsonParseError> for DbEntityParseFail {
         Self::JsonParseError(src)
     }
 }
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for DbEntityParseFail {
+    fn from(src: serde_json::Error) -> Self {
+        Self::SerdeJsonError(src)
+    }
+}
@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+
+use my_json::json_reader::JsonFirstLineReader;
+
+pub enum JsonFieldValue<'s> {
+    Null,
+    String(&'s [u8]),
+    Number(f64),
+    Bool(bool),
+    Object(&'s [u8]),
+    Array(&'s [u8]),
+}
+
+impl<'s> JsonFieldValue<'s> {
+    pub fn from_raw(raw: &'s [u8]) -> Option<Self> {
+        let first_byte = *raw.first()?;
+
+        match first_byte {
+            b'"' => {
+                if raw.len() < 2 {
+                    return None;
+                }
+                Some(Self::String(&raw[1..raw.len() - 1]))
+            }
+            b'{' => Some(Self::Object(raw)),
+            b'[' => Some(Self::Array(raw)),
+            _ => match raw {
+                b"null" => Some(Self::Null),
+                b"true" => Some(Self::Bool(true)),
+                b"false" => Some(Self::Bool(false)),
+                _ => {
+                    let number = std::str::from_utf8(raw).ok()?.parse().ok()?;
+                    Some(Self::Number(number))
+                }
+            },
+        }
+    }
+
+    pub fn as_string(&self) -> Option<Cow<'s, str>> {
+        match self {
+            Self::String(value) => unescape(value),
+            _ => None,
+        }
+    }
+}
+
+/// Finds raw value of the field. Path is a chain of field names which goes into nested objects
+pub fn find_json_field<'s, TName: AsRef<str>>(raw: &'s [u8], path: &[TName]) -> Option<&'s [u8]> {
+    let (field_name, rest_of_path) = path.split_first()?;
+
+    for line in JsonFirstLineReader::new(raw) {
+        let line = line.ok()?;
+
+        if line.get_name().ok()? != field_name.as_ref() {
+            continue;
+        }
+
+        let value = &raw[line.value_start..line.value_end];
+
+        if rest_of_path.is_empty() {
+            return Some(value);
+        }
+
+        if value.first() == Some(&b'{') {
+            return find_json_field(value, rest_of_path);
+        }
+
+        return None;
+    }
+
+    None
+}
+
+fn unescape(src: &[u8]) -> Option<Cow<'_, str>> {
+    let src = std::str::from_utf8(src).ok()?;
+
+    if !src.contains('\\') {
+        return Some(Cow::Borrowed(src));
+    }
+
+    let mut result = String::with_capacity(src.len());
+
+    let mut chars = src.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&code, 16).ok()?;
+                result.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            other => result.push(other),
+        }
+    }
+
+    Some(Cow::Owned(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_nested_field() {
+        let src = r#"{"PartitionKey":"pk","Address":{"City":"Paris","Zip":75001},"Tags":[1,2]}"#;
+
+        let value = find_json_field(src.as_bytes(), &["Address", "City"]).unwrap();
+        assert_eq!(
+            "Paris",
+            JsonFieldValue::from_raw(value)
+                .unwrap()
+                .as_string()
+                .unwrap()
+        );
+
+        let value = find_json_field(src.as_bytes(), &["Address", "Zip"]).unwrap();
+        assert!(matches!(
+            JsonFieldValue::from_raw(value),
+            Some(JsonFieldValue::Number(number)) if number == 75001.0
+        ));
+
+        assert!(find_json_field(src.as_bytes(), &["Tags", "Length"]).is_none());
+        assert!(find_json_field(src.as_bytes(), &["Unknown"]).is_none());
+    }
+
+    #[test]
+    fn test_unescape_string() {
+        let value = JsonFieldValue::from_raw(r#""a\"b\\c\u0041""#.as_bytes()).unwrap();
+
+        assert_eq!("a\"b\\cA", value.as_string().unwrap());
+    }
+}
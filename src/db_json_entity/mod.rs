@@ -1,16 +1,22 @@
 mod consts;
 mod date_time_injector;
 mod db_json_entity;
+#[cfg(feature = "serde")]
+mod db_json_entity_serde;
 mod error;
 #[cfg(feature = "master-node")]
 mod expires_update;
+mod json_field_value;
 mod json_key_value_position;
 mod json_time_stamp;
+mod projection;
 
 pub use date_time_injector::*;
 pub use db_json_entity::DbJsonEntity;
 pub use error::DbEntityParseFail;
 #[cfg(feature = "master-node")]
 pub use expires_update::*;
+pub use json_field_value::*;
 pub use json_key_value_position::*;
 pub use json_time_stamp::JsonTimeStamp;
+pub use projection::*;
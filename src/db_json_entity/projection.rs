@@ -0,0 +1,79 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+use super::consts;
+
+/// Builds JSON object which contains only requested fields. PartitionKey, RowKey and TimeStamp are always kept.
+/// Fields are written in the same order they have in the source JSON
+pub fn compile_projected_content<TName: AsRef<str>>(raw: &[u8], fields: &[TName]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(raw.len());
+
+    result.push(b'{');
+
+    for line in JsonFirstLineReader::new(raw) {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return raw.to_vec(),
+        };
+
+        let name = match line.get_name() {
+            Ok(name) => name,
+            Err(_) => return raw.to_vec(),
+        };
+
+        if !field_is_projected(name, fields) {
+            continue;
+        }
+
+        if result.len() > 1 {
+            result.push(b',');
+        }
+
+        result.extend_from_slice(&raw[line.name_start..line.name_end]);
+        result.push(b':');
+        result.extend_from_slice(&raw[line.value_start..line.value_end]);
+    }
+
+    result.push(b'}');
+
+    result
+}
+
+fn field_is_projected<TName: AsRef<str>>(name: &str, fields: &[TName]) -> bool {
+    if name == consts::PARTITION_KEY
+        || name == consts::ROW_KEY
+        || name == consts::TIME_STAMP
+        || name.to_lowercase() == consts::TIME_STAMP_LOWER_CASE
+    {
+        return true;
+    }
+
+    fields.iter().any(|field| field.as_ref() == name)
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_projection_keeps_system_fields_and_order() {
+        let src_json = r#"{"Amount": 5, "PartitionKey":"pk", "Name":"Test", "RowKey":"rk", "Status":{"A":1}, "TimeStamp":"2022-03-17T09:28:27.5923"}"#;
+
+        let result = super::compile_projected_content(src_json.as_bytes(), &["Status", "Amount"]);
+
+        assert_eq!(
+            r#"{"Amount":5,"PartitionKey":"pk","RowKey":"rk","Status":{"A":1},"TimeStamp":"2022-03-17T09:28:27.5923"}"#,
+            std::str::from_utf8(&result).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_projection_with_no_fields() {
+        let src_json = r#"{"PartitionKey":"pk","RowKey":"rk","Name":"Test"}"#;
+
+        let result = super::compile_projected_content::<&str>(src_json.as_bytes(), &[]);
+
+        assert_eq!(
+            r#"{"PartitionKey":"pk","RowKey":"rk"}"#,
+            std::str::from_utf8(&result).unwrap()
+        );
+    }
+}
@@ -0,0 +1,18 @@
+#[derive(Debug)]
+pub enum DbSnapshotError {
+    IoError(std::io::Error),
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    InvalidString,
+    InvalidLength(usize),
+    InvalidRecordType(u8),
+    InvalidSchemaFieldType(u8),
+    InvalidTombstoneRange(u8),
+    InvalidExpiresPosition,
+}
+
+impl From<std::io::Error> for DbSnapshotError {
+    fn from(src: std::io::Error) -> Self {
+        Self::IoError(src)
+    }
+}
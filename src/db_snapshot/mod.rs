@@ -0,0 +1,9 @@
+mod error;
+pub(crate) mod snapshot_format;
+mod snapshot_reader;
+mod snapshot_writer;
+
+pub use error::DbSnapshotError;
+pub use snapshot_format::SNAPSHOT_VERSION;
+pub use snapshot_reader::*;
+pub use snapshot_writer::*;
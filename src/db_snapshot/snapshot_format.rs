@@ -0,0 +1,212 @@
+use std::io::{Read, Write};
+
+use crate::validations::{DbTableSchema, SchemaField, SchemaFieldType};
+
+use super::DbSnapshotError;
+
+// Layout (all numbers are little endian):
+//   header:     MAGIC, version: u32
+//   table:      name, persist: u8, max_partitions_amount: opt u64,
+//               max_rows_per_partition_amount: opt u64, created: i64, tombstones_retention: opt u64,
+//               max_table_content_size: opt u64, max_partition_content_size: opt u64,
+//               sliding_expiration: opt (duration: u64, extend_partition: u8),
+//               default_row_ttl: opt u64,
+//               expiration_index: opt u64 - resolution of the bucketed index,
+//               schema: opt (max_row_size: opt u64, fields_amount: u32,
+//                            fields: name, field_type: u8, required: u8),
+//               sequence: u64
+//   partitions: RECORD_PARTITION, partition_key, expires: opt i64, sequence: u64,
+//               rows_amount: u64, rows...
+//   row:        row_key, data: bytes, time_stamp, expires: opt i64,
+//               expires_json_position: opt 4 * u64, last_read_access: i64, sequence: opt u64
+//   tombstones: RECORD_TOMBSTONE, range: u8 - 0 table, 1 partition, 2 row,
+//               partition_key (partition and row), row_key (row), deleted: i64, sequence: u64
+//   end:        RECORD_END
+// Strings and bytes are prefixed with u32 length. Optional values are prefixed with u8 0/1
+
+pub const MAGIC: &[u8; 4] = b"MNST";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+pub const RECORD_END: u8 = 0;
+pub const RECORD_PARTITION: u8 = 1;
+pub const RECORD_TOMBSTONE: u8 = 2;
+
+pub const TOMBSTONE_RANGE_TABLE: u8 = 0;
+pub const TOMBSTONE_RANGE_PARTITION: u8 = 1;
+pub const TOMBSTONE_RANGE_ROW: u8 = 2;
+
+// Protects from allocating memory for a garbage length of a damaged snapshot
+pub const MAX_BYTES_LEN: usize = 64 * 1024 * 1024;
+
+pub fn write_u8<W: Write>(writer: &mut W, value: u8) -> Result<(), DbSnapshotError> {
+    writer.write_all(&[value])?;
+    Ok(())
+}
+
+pub fn write_u32<W: Write>(writer: &mut W, value: u32) -> Result<(), DbSnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+pub fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<(), DbSnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+pub fn write_i64<W: Write>(writer: &mut W, value: i64) -> Result<(), DbSnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+/// Bytes longer than MAX_BYTES_LEN are not written: reader would not be able to read them back
+pub fn write_bytes<W: Write>(writer: &mut W, value: &[u8]) -> Result<(), DbSnapshotError> {
+    if value.len() > MAX_BYTES_LEN {
+        return Err(DbSnapshotError::InvalidLength(value.len()));
+    }
+
+    write_u32(writer, value.len() as u32)?;
+    writer.write_all(value)?;
+    Ok(())
+}
+
+pub fn write_opt_u64<W: Write>(writer: &mut W, value: Option<u64>) -> Result<(), DbSnapshotError> {
+    match value {
+        Some(value) => {
+            write_u8(writer, 1)?;
+            write_u64(writer, value)
+        }
+        None => write_u8(writer, 0),
+    }
+}
+
+pub fn write_opt_i64<W: Write>(writer: &mut W, value: Option<i64>) -> Result<(), DbSnapshotError> {
+    match value {
+        Some(value) => {
+            write_u8(writer, 1)?;
+            write_i64(writer, value)
+        }
+        None => write_u8(writer, 0),
+    }
+}
+
+pub fn read_u8<R: Read>(reader: &mut R) -> Result<u8, DbSnapshotError> {
+    let mut buffer = [0u8; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+pub fn read_u32<R: Read>(reader: &mut R) -> Result<u32, DbSnapshotError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+pub fn read_u64<R: Read>(reader: &mut R) -> Result<u64, DbSnapshotError> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+pub fn read_i64<R: Read>(reader: &mut R) -> Result<i64, DbSnapshotError> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(i64::from_le_bytes(buffer))
+}
+
+pub fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, DbSnapshotError> {
+    let len = read_u32(reader)? as usize;
+
+    if len > MAX_BYTES_LEN {
+        return Err(DbSnapshotError::InvalidLength(len));
+    }
+
+    let mut result = vec![0u8; len];
+    reader.read_exact(&mut result)?;
+    Ok(result)
+}
+
+pub fn read_string<R: Read>(reader: &mut R) -> Result<String, DbSnapshotError> {
+    let bytes = read_bytes(reader)?;
+    String::from_utf8(bytes).map_err(|_| DbSnapshotError::InvalidString)
+}
+
+pub fn read_opt_u64<R: Read>(reader: &mut R) -> Result<Option<u64>, DbSnapshotError> {
+    if read_u8(reader)? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(read_u64(reader)?))
+}
+
+pub fn read_opt_i64<R: Read>(reader: &mut R) -> Result<Option<i64>, DbSnapshotError> {
+    if read_u8(reader)? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(read_i64(reader)?))
+}
+
+pub fn write_opt_schema<W: Write>(
+    writer: &mut W,
+    schema: Option<&DbTableSchema>,
+) -> Result<(), DbSnapshotError> {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => return write_u8(writer, 0),
+    };
+
+    write_u8(writer, 1)?;
+    write_opt_u64(writer, schema.max_row_size.map(|itm| itm as u64))?;
+    write_u32(writer, schema.fields.len() as u32)?;
+
+    for field in &schema.fields {
+        write_bytes(writer, field.name.as_bytes())?;
+
+        let field_type = match field.field_type {
+            SchemaFieldType::String => 0,
+            SchemaFieldType::Number => 1,
+            SchemaFieldType::Bool => 2,
+            SchemaFieldType::Object => 3,
+            SchemaFieldType::Array => 4,
+            SchemaFieldType::DateTime => 5,
+        };
+
+        write_u8(writer, field_type)?;
+        write_u8(writer, field.required as u8)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_opt_schema<R: Read>(reader: &mut R) -> Result<Option<DbTableSchema>, DbSnapshotError> {
+    if read_u8(reader)? == 0 {
+        return Ok(None);
+    }
+
+    let mut schema = DbTableSchema::new();
+    schema.max_row_size = read_opt_u64(reader)?.map(|itm| itm as usize);
+
+    let fields_amount = read_u32(reader)?;
+
+    for _ in 0..fields_amount {
+        let name = read_string(reader)?;
+
+        let field_type = match read_u8(reader)? {
+            0 => SchemaFieldType::String,
+            1 => SchemaFieldType::Number,
+            2 => SchemaFieldType::Bool,
+            3 => SchemaFieldType::Object,
+            4 => SchemaFieldType::Array,
+            5 => SchemaFieldType::DateTime,
+            field_type => return Err(DbSnapshotError::InvalidSchemaFieldType(field_type)),
+        };
+
+        schema.fields.push(SchemaField {
+            name,
+            field_type,
+            required: read_u8(reader)? != 0,
+        });
+    }
+
+    Ok(Some(schema))
+}
@@ -0,0 +1,495 @@
+use std::{io::Read, sync::Arc, time::Duration};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{
+    db::{
+        DbPartition, DbRow, DbTable, DbTableAttributes, DbTombstone, DbTombstoneRange,
+        SlidingExpiration,
+    },
+    db_json_entity::JsonKeyValuePosition,
+    ExpirationIndexKind,
+};
+
+use super::{snapshot_format::*, DbSnapshotError};
+
+pub enum DbSnapshotRecord {
+    Partition {
+        partition_key: String,
+        db_partition: Box<DbPartition>,
+    },
+    Tombstone(DbTombstone),
+}
+
+pub struct DbTableSnapshotReader<R: Read> {
+    reader: R,
+    expiration_index: ExpirationIndexKind,
+}
+
+impl<R: Read> DbTableSnapshotReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            expiration_index: ExpirationIndexKind::BTree,
+        }
+    }
+
+    /// Restores the table with all the indexes. JSON of the rows is not parsed
+    pub fn read_table(mut self) -> Result<DbTable, DbSnapshotError> {
+        let (table_name, attributes, sequence) = self.read_header()?;
+
+        let mut db_table = DbTable::new(table_name, attributes);
+        db_table.sequence = sequence;
+
+        while let Some(record) = self.read_record()? {
+            match record {
+                DbSnapshotRecord::Partition {
+                    partition_key,
+                    db_partition,
+                } => {
+                    db_table.init_partition(partition_key, *db_partition);
+                }
+                DbSnapshotRecord::Tombstone(tombstone) => {
+                    db_table.tombstones.add(tombstone);
+                }
+            }
+        }
+
+        Ok(db_table)
+    }
+
+    /// Returns table name, attributes and sequence of the table
+    pub fn read_header(&mut self) -> Result<(String, DbTableAttributes, u64), DbSnapshotError> {
+        let mut magic = [0u8; 4];
+        self.reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(DbSnapshotError::InvalidHeader);
+        }
+
+        let version = read_u32(&mut self.reader)?;
+
+        if version != SNAPSHOT_VERSION {
+            return Err(DbSnapshotError::UnsupportedVersion(version));
+        }
+
+        let table_name = read_string(&mut self.reader)?;
+        let persist = read_u8(&mut self.reader)? != 0;
+        let max_partitions_amount = read_opt_u64(&mut self.reader)?.map(|itm| itm as usize);
+        let max_rows_per_partition_amount = read_opt_u64(&mut self.reader)?.map(|itm| itm as usize);
+        let created = DateTimeAsMicroseconds::new(read_i64(&mut self.reader)?);
+
+        let mut attributes = DbTableAttributes::new(
+            persist,
+            max_partitions_amount,
+            max_rows_per_partition_amount,
+            created,
+        );
+
+        attributes.tombstones_retention =
+            read_opt_u64(&mut self.reader)?.map(Duration::from_micros);
+
+        attributes.max_table_content_size = read_opt_u64(&mut self.reader)?.map(|itm| itm as usize);
+        attributes.max_partition_content_size =
+            read_opt_u64(&mut self.reader)?.map(|itm| itm as usize);
+
+        if read_u8(&mut self.reader)? != 0 {
+            attributes.sliding_expiration = Some(SlidingExpiration {
+                duration: Duration::from_micros(read_u64(&mut self.reader)?),
+                extend_partition: read_u8(&mut self.reader)? != 0,
+            });
+        }
+
+        attributes.default_row_ttl = read_opt_u64(&mut self.reader)?.map(Duration::from_micros);
+
+        if let Some(resolution) = read_opt_u64(&mut self.reader)? {
+            attributes.expiration_index = ExpirationIndexKind::Bucketed {
+                resolution: Duration::from_micros(resolution),
+            };
+        }
+
+        attributes.schema = read_opt_schema(&mut self.reader)?;
+
+        let sequence = read_u64(&mut self.reader)?;
+
+        self.expiration_index = attributes.expiration_index;
+
+        Ok((table_name, attributes, sequence))
+    }
+
+    /// Returns None when all the records are read
+    pub fn read_record(&mut self) -> Result<Option<DbSnapshotRecord>, DbSnapshotError> {
+        match read_u8(&mut self.reader)? {
+            RECORD_END => Ok(None),
+            RECORD_PARTITION => Ok(Some(self.read_partition()?)),
+            RECORD_TOMBSTONE => Ok(Some(DbSnapshotRecord::Tombstone(self.read_tombstone()?))),
+            record_type => Err(DbSnapshotError::InvalidRecordType(record_type)),
+        }
+    }
+
+    fn read_partition(&mut self) -> Result<DbSnapshotRecord, DbSnapshotError> {
+        let partition_key = read_string(&mut self.reader)?;
+
+        let mut db_partition = DbPartition::new_with_expiration_index(self.expiration_index);
+        db_partition.expires = read_opt_i64(&mut self.reader)?.map(DateTimeAsMicroseconds::new);
+        db_partition.set_sequence(read_u64(&mut self.reader)?);
+
+        let rows_amount = read_u64(&mut self.reader)?;
+
+        for _ in 0..rows_amount {
+            let (db_row, sequence) = self.read_row(partition_key.as_str())?;
+            let row_key = db_row.row_key.to_string();
+            db_partition.insert_or_replace_row(Arc::new(db_row));
+
+            if let Some(sequence) = sequence {
+                db_partition.set_row_sequence(&row_key, sequence);
+            }
+        }
+
+        Ok(DbSnapshotRecord::Partition {
+            partition_key,
+            db_partition: Box::new(db_partition),
+        })
+    }
+
+    fn read_tombstone(&mut self) -> Result<DbTombstone, DbSnapshotError> {
+        let range = match read_u8(&mut self.reader)? {
+            TOMBSTONE_RANGE_TABLE => DbTombstoneRange::Table,
+            TOMBSTONE_RANGE_PARTITION => {
+                DbTombstoneRange::Partition(read_string(&mut self.reader)?)
+            }
+            TOMBSTONE_RANGE_ROW => DbTombstoneRange::Row {
+                partition_key: read_string(&mut self.reader)?,
+                row_key: read_string(&mut self.reader)?,
+            },
+            range => return Err(DbSnapshotError::InvalidTombstoneRange(range)),
+        };
+
+        Ok(DbTombstone {
+            range,
+            deleted: DateTimeAsMicroseconds::new(read_i64(&mut self.reader)?),
+            sequence: read_u64(&mut self.reader)?,
+        })
+    }
+
+    fn read_row(&mut self, partition_key: &str) -> Result<(DbRow, Option<u64>), DbSnapshotError> {
+        let row_key = read_string(&mut self.reader)?;
+        let data = read_bytes(&mut self.reader)?;
+        let time_stamp = read_string(&mut self.reader)?;
+        let expires = read_opt_i64(&mut self.reader)?.map(DateTimeAsMicroseconds::new);
+
+        let expires_json_position = if read_u8(&mut self.reader)? == 0 {
+            None
+        } else {
+            let position = JsonKeyValuePosition {
+                key_start: read_u64(&mut self.reader)? as usize,
+                key_end: read_u64(&mut self.reader)? as usize,
+                value_start: read_u64(&mut self.reader)? as usize,
+                value_end: read_u64(&mut self.reader)? as usize,
+            };
+
+            // Position is used to slice the JSON later, so damaged one must not get into the row
+            if position.key_start > position.key_end
+                || position.key_end > position.value_start
+                || position.value_start > position.value_end
+                || position.value_end > data.len()
+            {
+                return Err(DbSnapshotError::InvalidExpiresPosition);
+            }
+
+            Some(position)
+        };
+
+        let last_read_access = DateTimeAsMicroseconds::new(read_i64(&mut self.reader)?);
+        let sequence = read_opt_u64(&mut self.reader)?;
+
+        let db_row = DbRow::restore(
+            partition_key.to_string(),
+            row_key,
+            data,
+            expires,
+            expires_json_position,
+            time_stamp,
+            last_read_access,
+        );
+
+        Ok((db_row, sequence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use crate::{
+        db::{DbTable, DbTableAttributes, SlidingExpiration},
+        db_json_entity::JsonTimeStamp,
+        db_snapshot::{
+            snapshot_format::{write_bytes, MAX_BYTES_LEN},
+            DbSnapshotError, DbTableSnapshotReader, DbTableSnapshotWriter, SNAPSHOT_VERSION,
+        },
+        test_utils::{create_db_row_from_json, create_db_table, create_schema},
+        ExpirationIndexKind,
+    };
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut attributes = DbTableAttributes::create_default();
+        attributes.max_partitions_amount = Some(10);
+        attributes.max_partition_content_size = Some(1024);
+        attributes.sliding_expiration = Some(SlidingExpiration {
+            duration: Duration::from_secs(300),
+            extend_partition: true,
+        });
+        attributes.tombstones_retention = Some(Duration::from_secs(60));
+        attributes.expiration_index = ExpirationIndexKind::Bucketed {
+            resolution: Duration::from_secs(1),
+        };
+
+        let mut schema = create_schema();
+        schema.max_row_size = Some(4096);
+        attributes.schema = Some(schema);
+
+        let mut db_table = DbTable::new("test-table".to_string(), attributes);
+
+        let time_stamp = JsonTimeStamp::from_date_time(DateTimeAsMicroseconds::new(1_000_000));
+
+        for (partition_key, row_key, expires) in [
+            ("pk1", "rk1", None),
+            ("pk1", "rk2", Some("2030-01-01T00:00:00")),
+            ("pk2", "rk1", None),
+            ("pk3", "rk1", None),
+        ] {
+            let json = match expires {
+                Some(expires) => format!(
+                    r#"{{"PartitionKey":"{}","RowKey":"{}","Name":"test","Amount":1,"Expires":"{}"}}"#,
+                    partition_key, row_key, expires
+                ),
+                None => format!(
+                    r#"{{"PartitionKey":"{}","RowKey":"{}","Name":"test","Amount":1}}"#,
+                    partition_key, row_key
+                ),
+            };
+
+            db_table
+                .insert_row(&create_db_row_from_json(&json, &time_stamp), None)
+                .unwrap();
+        }
+
+        db_table.remove_row(
+            &"pk1".to_string(),
+            "rk1",
+            false,
+            Some(DateTimeAsMicroseconds::new(2_000_000)),
+        );
+        db_table.remove_partition(
+            &"pk3".to_string(),
+            Some(DateTimeAsMicroseconds::new(3_000_000)),
+        );
+
+        db_table.update_partition_expiration("pk2", Some(DateTimeAsMicroseconds::new(5_000_000)));
+        db_table.attributes.default_row_ttl = Some(Duration::from_secs(3600));
+
+        let snapshot = DbTableSnapshotWriter::new(Vec::new())
+            .write_table(&db_table)
+            .unwrap();
+
+        let mut restored = DbTableSnapshotReader::new(snapshot.as_slice())
+            .read_table()
+            .unwrap();
+
+        // Restored table is what is persisted already
+        assert!(restored.partitions.drain_dirty_partitions().is_none());
+
+        assert_eq!("test-table", restored.name);
+        assert_eq!(Some(10), restored.attributes.max_partitions_amount);
+        assert_eq!(Some(1024), restored.attributes.max_partition_content_size);
+        assert_eq!(None, restored.attributes.max_table_content_size);
+        assert_eq!(
+            db_table.attributes.sliding_expiration,
+            restored.attributes.sliding_expiration
+        );
+        assert_eq!(
+            Some(Duration::from_secs(3600)),
+            restored.attributes.default_row_ttl
+        );
+        assert_eq!(
+            db_table.attributes.expiration_index,
+            restored.attributes.expiration_index
+        );
+        assert_eq!(db_table.attributes.schema, restored.attributes.schema);
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            restored.attributes.tombstones_retention
+        );
+        assert_eq!(
+            db_table.attributes.created.unix_microseconds,
+            restored.attributes.created.unix_microseconds
+        );
+
+        assert_eq!(db_table.get_sequence(), restored.get_sequence());
+        assert_eq!(
+            db_table.get_tombstones().len(),
+            restored.get_tombstones().len()
+        );
+        assert_eq!(
+            db_table
+                .get_tombstones()
+                .get("pk1", "rk1")
+                .unwrap()
+                .sequence,
+            restored
+                .get_tombstones()
+                .get("pk1", "rk1")
+                .unwrap()
+                .sequence
+        );
+        assert_eq!(
+            3_000_000,
+            restored
+                .get_tombstones()
+                .get_partition("pk3")
+                .unwrap()
+                .get_removed()
+                .unwrap()
+                .deleted
+                .unix_microseconds
+        );
+
+        assert_eq!(db_table.get_rows_amount(), restored.get_rows_amount());
+        assert_eq!(db_table.get_table_size(), restored.get_table_size());
+        assert_eq!(1, restored.get_expiration_index_rows_amount());
+
+        let partitions_to_expire = restored
+            .partitions
+            .get_partitions_to_expire(DateTimeAsMicroseconds::new(6_000_000))
+            .unwrap();
+        assert_eq!(vec!["pk2"], partitions_to_expire);
+
+        for db_row in db_table.get_all_rows() {
+            let restored_row = restored
+                .get_partition(&db_row.partition_key)
+                .unwrap()
+                .get_row(&db_row.row_key)
+                .unwrap();
+
+            let db_partition = db_table.get_partition(&db_row.partition_key).unwrap();
+            let restored_partition = restored.get_partition(&db_row.partition_key).unwrap();
+
+            assert_eq!(
+                db_partition.get_sequence(),
+                restored_partition.get_sequence()
+            );
+            assert_eq!(
+                db_partition.get_row_sequence(&db_row.row_key),
+                restored_partition.get_row_sequence(&db_row.row_key)
+            );
+
+            assert_eq!(db_row.data, restored_row.data);
+            assert_eq!(db_row.time_stamp, restored_row.time_stamp);
+            assert_eq!(
+                db_row.expires.map(|itm| itm.unix_microseconds),
+                restored_row.expires.map(|itm| itm.unix_microseconds)
+            );
+            assert_eq!(
+                db_row
+                    .expires_json_position
+                    .as_ref()
+                    .map(|itm| (itm.value_start, itm.value_end)),
+                restored_row
+                    .expires_json_position
+                    .as_ref()
+                    .map(|itm| (itm.value_start, itm.value_end))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let result = DbTableSnapshotReader::new(&b"JSON[]"[..]).read_table();
+        assert!(matches!(result, Err(DbSnapshotError::InvalidHeader)));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut snapshot = b"MNST".to_vec();
+        snapshot.extend_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+
+        let result = DbTableSnapshotReader::new(snapshot.as_slice()).read_table();
+        assert!(
+            matches!(result, Err(DbSnapshotError::UnsupportedVersion(version)) if version == SNAPSHOT_VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let mut snapshot = b"MNST".to_vec();
+        snapshot.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        snapshot.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let result = DbTableSnapshotReader::new(snapshot.as_slice()).read_table();
+        assert!(matches!(result, Err(DbSnapshotError::InvalidLength(_))));
+    }
+
+    #[test]
+    fn test_too_long_bytes_are_not_written() {
+        let mut writer = Vec::new();
+        let value = vec![0u8; MAX_BYTES_LEN + 1];
+
+        let result = write_bytes(&mut writer, &value);
+
+        assert!(
+            matches!(result, Err(DbSnapshotError::InvalidLength(len)) if len == MAX_BYTES_LEN + 1)
+        );
+        assert!(writer.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_expires_position() {
+        let mut db_table = create_db_table();
+
+        let json = r#"{"PartitionKey":"pk1","RowKey":"rk1","Expires":"2030-01-01T00:00:00"}"#;
+        db_table
+            .insert_row(&create_db_row_from_json(json, &JsonTimeStamp::now()), None)
+            .unwrap();
+
+        let position = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk1")
+            .unwrap()
+            .expires_json_position
+            .clone()
+            .unwrap();
+
+        let mut snapshot = DbTableSnapshotWriter::new(Vec::new())
+            .write_table(&db_table)
+            .unwrap();
+
+        let mut encoded = Vec::new();
+        for value in [
+            position.key_start,
+            position.key_end,
+            position.value_start,
+            position.value_end,
+        ] {
+            encoded.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+
+        let offset = snapshot
+            .windows(encoded.len())
+            .position(|itm| itm == encoded.as_slice())
+            .unwrap();
+
+        // value_end is beyond the JSON of the row
+        snapshot[offset + 24..offset + 32].copy_from_slice(&10_000u64.to_le_bytes());
+
+        let result = DbTableSnapshotReader::new(snapshot.as_slice()).read_table();
+        assert!(matches!(
+            result,
+            Err(DbSnapshotError::InvalidExpiresPosition)
+        ));
+    }
+}
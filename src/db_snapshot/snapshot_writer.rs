@@ -0,0 +1,191 @@
+use std::io::Write;
+
+use crate::{
+    db::{DbPartition, DbRow, DbTable, DbTableAttributes, DbTombstone, DbTombstoneRange},
+    ExpirationIndexKind,
+};
+
+use super::{snapshot_format::*, DbSnapshotError};
+
+pub struct DbTableSnapshotWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> DbTableSnapshotWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_table(mut self, db_table: &DbTable) -> Result<W, DbSnapshotError> {
+        self.write_header(
+            db_table.name.as_str(),
+            &db_table.attributes,
+            db_table.get_sequence(),
+        )?;
+
+        for (partition_key, db_partition) in db_table.partitions.get_all() {
+            self.write_partition(partition_key, db_partition)?;
+        }
+
+        for tombstone in db_table.get_tombstones().iter() {
+            self.write_tombstone(tombstone)?;
+        }
+
+        self.finish()
+    }
+
+    pub fn write_header(
+        &mut self,
+        table_name: &str,
+        attributes: &DbTableAttributes,
+        sequence: u64,
+    ) -> Result<(), DbSnapshotError> {
+        self.writer.write_all(MAGIC)?;
+        write_u32(&mut self.writer, SNAPSHOT_VERSION)?;
+
+        write_bytes(&mut self.writer, table_name.as_bytes())?;
+        write_u8(&mut self.writer, attributes.persist as u8)?;
+        write_opt_u64(
+            &mut self.writer,
+            attributes.max_partitions_amount.map(|itm| itm as u64),
+        )?;
+        write_opt_u64(
+            &mut self.writer,
+            attributes
+                .max_rows_per_partition_amount
+                .map(|itm| itm as u64),
+        )?;
+        write_i64(&mut self.writer, attributes.created.unix_microseconds)?;
+        write_opt_u64(
+            &mut self.writer,
+            attributes
+                .tombstones_retention
+                .map(|itm| itm.as_micros() as u64),
+        )?;
+        write_opt_u64(
+            &mut self.writer,
+            attributes.max_table_content_size.map(|itm| itm as u64),
+        )?;
+        write_opt_u64(
+            &mut self.writer,
+            attributes.max_partition_content_size.map(|itm| itm as u64),
+        )?;
+
+        match &attributes.sliding_expiration {
+            Some(sliding_expiration) => {
+                write_u8(&mut self.writer, 1)?;
+                write_u64(
+                    &mut self.writer,
+                    sliding_expiration.duration.as_micros() as u64,
+                )?;
+                write_u8(&mut self.writer, sliding_expiration.extend_partition as u8)?;
+            }
+            None => {
+                write_u8(&mut self.writer, 0)?;
+            }
+        }
+
+        write_opt_u64(
+            &mut self.writer,
+            attributes.default_row_ttl.map(|itm| itm.as_micros() as u64),
+        )?;
+
+        let expiration_index_resolution = match attributes.expiration_index {
+            ExpirationIndexKind::BTree => None,
+            ExpirationIndexKind::Bucketed { resolution } => Some(resolution.as_micros() as u64),
+        };
+
+        write_opt_u64(&mut self.writer, expiration_index_resolution)?;
+
+        write_opt_schema(&mut self.writer, attributes.schema.as_ref())?;
+
+        write_u64(&mut self.writer, sequence)?;
+
+        Ok(())
+    }
+
+    pub fn write_partition(
+        &mut self,
+        partition_key: &str,
+        db_partition: &DbPartition,
+    ) -> Result<(), DbSnapshotError> {
+        write_u8(&mut self.writer, RECORD_PARTITION)?;
+        write_bytes(&mut self.writer, partition_key.as_bytes())?;
+        write_opt_i64(
+            &mut self.writer,
+            db_partition.expires.map(|itm| itm.unix_microseconds),
+        )?;
+        write_u64(&mut self.writer, db_partition.get_sequence())?;
+        write_u64(&mut self.writer, db_partition.get_rows_amount() as u64)?;
+
+        for db_row in db_partition.get_all_rows() {
+            self.write_row(db_row, db_partition.get_row_sequence(&db_row.row_key))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_tombstone(&mut self, tombstone: &DbTombstone) -> Result<(), DbSnapshotError> {
+        write_u8(&mut self.writer, RECORD_TOMBSTONE)?;
+
+        match &tombstone.range {
+            DbTombstoneRange::Table => {
+                write_u8(&mut self.writer, TOMBSTONE_RANGE_TABLE)?;
+            }
+            DbTombstoneRange::Partition(partition_key) => {
+                write_u8(&mut self.writer, TOMBSTONE_RANGE_PARTITION)?;
+                write_bytes(&mut self.writer, partition_key.as_bytes())?;
+            }
+            DbTombstoneRange::Row {
+                partition_key,
+                row_key,
+            } => {
+                write_u8(&mut self.writer, TOMBSTONE_RANGE_ROW)?;
+                write_bytes(&mut self.writer, partition_key.as_bytes())?;
+                write_bytes(&mut self.writer, row_key.as_bytes())?;
+            }
+        }
+
+        write_i64(&mut self.writer, tombstone.deleted.unix_microseconds)?;
+        write_u64(&mut self.writer, tombstone.sequence)?;
+
+        Ok(())
+    }
+
+    fn write_row(&mut self, db_row: &DbRow, sequence: Option<u64>) -> Result<(), DbSnapshotError> {
+        write_bytes(&mut self.writer, db_row.row_key.as_bytes())?;
+        write_bytes(&mut self.writer, db_row.data.as_slice())?;
+        write_bytes(&mut self.writer, db_row.time_stamp.as_bytes())?;
+        write_opt_i64(
+            &mut self.writer,
+            db_row.expires.map(|itm| itm.unix_microseconds),
+        )?;
+
+        match &db_row.expires_json_position {
+            Some(position) => {
+                write_u8(&mut self.writer, 1)?;
+                write_u64(&mut self.writer, position.key_start as u64)?;
+                write_u64(&mut self.writer, position.key_end as u64)?;
+                write_u64(&mut self.writer, position.value_start as u64)?;
+                write_u64(&mut self.writer, position.value_end as u64)?;
+            }
+            None => {
+                write_u8(&mut self.writer, 0)?;
+            }
+        }
+
+        write_i64(
+            &mut self.writer,
+            db_row.last_read_access.get_unix_microseconds(),
+        )?;
+        write_opt_u64(&mut self.writer, sequence)?;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, DbSnapshotError> {
+        write_u8(&mut self.writer, RECORD_END)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
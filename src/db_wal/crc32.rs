@@ -0,0 +1,24 @@
+// CRC-32 (IEEE 802.3). Records are small, so bitwise calculation is good enough
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for b in data {
+        crc ^= *b as u32;
+
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0, super::crc32(b""));
+        assert_eq!(0xCBF4_3926, super::crc32(b"123456789"));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    db_json_entity::DbEntityParseFail, db_snapshot::DbSnapshotError,
+    validations::SchemaValidationError,
+};
+
+#[derive(Debug)]
+pub enum DbWalError {
+    IoError(std::io::Error),
+    SnapshotError(DbSnapshotError),
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    /// Corrupted record is not the last one in the log
+    CorruptedRecord,
+    RecordIsTooLarge(usize),
+    InvalidRecordType(u8),
+    InvalidRecord,
+    InvalidRow(DbEntityParseFail),
+    SchemaValidationError(SchemaValidationError),
+}
+
+impl From<std::io::Error> for DbWalError {
+    fn from(src: std::io::Error) -> Self {
+        Self::IoError(src)
+    }
+}
+
+impl From<DbSnapshotError> for DbWalError {
+    fn from(src: DbSnapshotError) -> Self {
+        Self::SnapshotError(src)
+    }
+}
+
+impl From<DbEntityParseFail> for DbWalError {
+    fn from(src: DbEntityParseFail) -> Self {
+        Self::InvalidRow(src)
+    }
+}
+
+impl From<SchemaValidationError> for DbWalError {
+    fn from(src: SchemaValidationError) -> Self {
+        Self::SchemaValidationError(src)
+    }
+}
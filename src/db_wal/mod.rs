@@ -0,0 +1,12 @@
+mod crc32;
+mod error;
+mod wal_reader;
+mod wal_record;
+mod wal_replay;
+mod wal_writer;
+
+pub use error::DbWalError;
+pub use wal_reader::*;
+pub use wal_record::*;
+pub use wal_replay::*;
+pub use wal_writer::*;
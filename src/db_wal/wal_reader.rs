@@ -0,0 +1,267 @@
+use std::io::{ErrorKind, Read};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::{crc32::crc32, DbWalError, DbWalRecord, MAX_WAL_RECORD_SIZE, WAL_MAGIC, WAL_VERSION};
+
+pub struct DbWalEntry {
+    pub moment: DateTimeAsMicroseconds,
+    pub record: DbWalRecord,
+}
+
+pub struct DbWalReader<R: Read> {
+    reader: R,
+    header_is_read: bool,
+    torn_tail: bool,
+    valid_len: u64,
+}
+
+impl<R: Read> DbWalReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            header_is_read: false,
+            torn_tail: false,
+            valid_len: 0,
+        }
+    }
+
+    /// Returns None at the end of the log. Incomplete or corrupted record at the very end
+    /// of the log is treated as the end of the log, since it's a write interrupted by the crash.
+    /// Corrupted record followed by more data is an error
+    pub fn read_next(&mut self) -> Result<Option<DbWalEntry>, DbWalError> {
+        if self.torn_tail {
+            return Ok(None);
+        }
+
+        if !self.header_is_read {
+            if !self.read_header()? {
+                return Ok(None);
+            }
+
+            self.header_is_read = true;
+            self.valid_len = (WAL_MAGIC.len() + 4) as u64;
+        }
+
+        let mut header = [0u8; 8];
+
+        match read_exact_or_eof(&mut self.reader, &mut header)? {
+            ReadResult::Eof => return Ok(None),
+            ReadResult::Torn => {
+                self.torn_tail = true;
+                return Ok(None);
+            }
+            ReadResult::Ok => {}
+        }
+
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        if len > MAX_WAL_RECORD_SIZE {
+            // Payload is skipped without allocating, just to find out if the log ends here
+            let skipped = std::io::copy(
+                &mut self.reader.by_ref().take(len as u64),
+                &mut std::io::sink(),
+            )?;
+
+            if skipped < len as u64 {
+                self.torn_tail = true;
+                return Ok(None);
+            }
+
+            return Err(DbWalError::CorruptedRecord);
+        }
+
+        let mut payload = vec![0u8; len];
+
+        match read_exact_or_eof(&mut self.reader, &mut payload)? {
+            ReadResult::Ok => {}
+            _ => {
+                self.torn_tail = true;
+                return Ok(None);
+            }
+        }
+
+        if len < 8 || crc32(payload.as_slice()) != checksum {
+            if self.is_at_eof()? {
+                self.torn_tail = true;
+                return Ok(None);
+            }
+
+            return Err(DbWalError::CorruptedRecord);
+        }
+
+        let moment = i64::from_le_bytes(payload[..8].try_into().unwrap());
+        let record = DbWalRecord::deserialize(&payload[8..])?;
+
+        self.valid_len += (header.len() + len) as u64;
+
+        Ok(Some(DbWalEntry {
+            moment: DateTimeAsMicroseconds::new(moment),
+            record,
+        }))
+    }
+
+    /// True if the log ended with the incomplete or corrupted record
+    pub fn has_torn_tail(&self) -> bool {
+        self.torn_tail
+    }
+
+    /// Length of the log up to the end of the last record read. Log has to be truncated
+    /// to it before it is continued, otherwise the torn tail ends up in the middle of the log
+    pub fn get_valid_len(&self) -> u64 {
+        self.valid_len
+    }
+
+    /// Returns false if the log is empty or the header itself was interrupted by the crash
+    fn read_header(&mut self) -> Result<bool, DbWalError> {
+        let mut header = [0u8; 8];
+
+        match read_exact_or_eof(&mut self.reader, &mut header)? {
+            ReadResult::Eof => return Ok(false),
+            ReadResult::Torn => {
+                self.torn_tail = true;
+                return Ok(false);
+            }
+            ReadResult::Ok => {}
+        }
+
+        if &header[..4] != WAL_MAGIC {
+            return Err(DbWalError::InvalidHeader);
+        }
+
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        if version != WAL_VERSION {
+            return Err(DbWalError::UnsupportedVersion(version));
+        }
+
+        Ok(true)
+    }
+
+    fn is_at_eof(&mut self) -> Result<bool, DbWalError> {
+        let mut buffer = [0u8; 1];
+        let result = read_exact_or_eof(&mut self.reader, &mut buffer)?;
+        Ok(matches!(result, ReadResult::Eof))
+    }
+}
+
+enum ReadResult {
+    Ok,
+    Eof,
+    Torn,
+}
+
+fn read_exact_or_eof<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<ReadResult, DbWalError> {
+    let mut read = 0;
+
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => {
+                if read == 0 {
+                    return Ok(ReadResult::Eof);
+                }
+                return Ok(ReadResult::Torn);
+            }
+            Ok(n) => read += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(ReadResult::Ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use crate::db_wal::{DbWalError, DbWalRecord, DbWalWriter};
+
+    use super::DbWalReader;
+
+    fn create_wal(records_amount: usize) -> Vec<u8> {
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+
+        for _ in 0..records_amount {
+            wal_writer
+                .append(
+                    DateTimeAsMicroseconds::new(1_000_000),
+                    &DbWalRecord::ClearTable,
+                )
+                .unwrap();
+        }
+
+        wal_writer.into_inner()
+    }
+
+    fn read_all(wal: &[u8]) -> Result<(usize, bool), DbWalError> {
+        let mut reader = DbWalReader::new(wal);
+        let mut amount = 0;
+
+        while reader.read_next()?.is_some() {
+            amount += 1;
+        }
+
+        Ok((amount, reader.has_torn_tail()))
+    }
+
+    #[test]
+    fn test_empty_log() {
+        assert_eq!((0, false), read_all(&[]).unwrap());
+        assert_eq!((0, false), read_all(create_wal(0).as_slice()).unwrap());
+        assert_eq!((0, true), read_all(&create_wal(0)[..5]).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert!(matches!(
+            read_all(b"JSON[]{}"),
+            Err(DbWalError::InvalidHeader)
+        ));
+
+        let mut wal = create_wal(1);
+        wal[4] = 2;
+
+        assert!(matches!(
+            read_all(wal.as_slice()),
+            Err(DbWalError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_corrupted_record_in_the_middle() {
+        let mut wal = create_wal(3);
+        let frame_len = (wal.len() - 8) / 3;
+
+        // Last byte of the second frame
+        wal[8 + frame_len * 2 - 1] ^= 0xFF;
+
+        assert!(matches!(
+            read_all(wal.as_slice()),
+            Err(DbWalError::CorruptedRecord)
+        ));
+
+        // The same corruption at the end of the log is a torn tail
+        wal.truncate(8 + frame_len * 2);
+        assert_eq!((1, true), read_all(wal.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_huge_record_length() {
+        let mut wal = create_wal(2);
+        let frame_len = (wal.len() - 8) / 2;
+
+        wal[8 + frame_len..8 + frame_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!((1, true), read_all(wal.as_slice()).unwrap());
+
+        let mut wal = create_wal(1);
+        wal[8..12].copy_from_slice(&(super::MAX_WAL_RECORD_SIZE as u32 + 1).to_le_bytes());
+        wal.extend_from_slice(&vec![0u8; super::MAX_WAL_RECORD_SIZE + 1]);
+
+        assert!(matches!(
+            read_all(wal.as_slice()),
+            Err(DbWalError::CorruptedRecord)
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+use std::{sync::Arc, time::Duration};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{
+    db::{DbRow, DbTable, SlidingExpiration},
+    db_json_entity::DbJsonEntity,
+    db_snapshot::snapshot_format::*,
+    validations::DbTableSchema,
+};
+
+use super::DbWalError;
+
+const RECORD_INSERT_ROW: u8 = 1;
+const RECORD_INSERT_OR_REPLACE_ROW: u8 = 2;
+const RECORD_BULK_INSERT_OR_REPLACE: u8 = 3;
+const RECORD_REMOVE_ROW: u8 = 4;
+const RECORD_BULK_REMOVE_ROWS: u8 = 5;
+const RECORD_REMOVE_PARTITION: u8 = 6;
+const RECORD_CLEAR_TABLE: u8 = 7;
+const RECORD_UPDATE_PARTITION_EXPIRATION: u8 = 8;
+const RECORD_UPDATE_ROW_EXPIRATION: u8 = 9;
+const RECORD_UPDATE_ATTRIBUTES: u8 = 10;
+const RECORD_UPDATE_CONTENT_SIZE_BUDGETS: u8 = 11;
+const RECORD_UPDATE_SLIDING_EXPIRATION: u8 = 12;
+const RECORD_UPDATE_DEFAULT_ROW_TTL: u8 = 13;
+const RECORD_UPDATE_SCHEMA: u8 = 14;
+
+pub enum DbWalRecord {
+    InsertRow(Arc<DbRow>),
+    InsertOrReplaceRow(Arc<DbRow>),
+    BulkInsertOrReplace {
+        partition_key: String,
+        db_rows: Vec<Arc<DbRow>>,
+    },
+    RemoveRow {
+        partition_key: String,
+        row_key: String,
+        delete_empty_partition: bool,
+    },
+    BulkRemoveRows {
+        partition_key: String,
+        row_keys: Vec<String>,
+        delete_empty_partition: bool,
+    },
+    RemovePartition {
+        partition_key: String,
+    },
+    ClearTable,
+    UpdatePartitionExpiration {
+        partition_key: String,
+        expires: Option<DateTimeAsMicroseconds>,
+    },
+    UpdateRowExpiration {
+        partition_key: String,
+        row_key: String,
+        expires: Option<DateTimeAsMicroseconds>,
+    },
+    UpdateAttributes {
+        persist: bool,
+        max_partitions_amount: Option<usize>,
+        max_rows_per_partition_amount: Option<usize>,
+        tombstones_retention: Option<Duration>,
+    },
+    UpdateContentSizeBudgets {
+        max_table_content_size: Option<usize>,
+        max_partition_content_size: Option<usize>,
+    },
+    UpdateSlidingExpiration(Option<SlidingExpiration>),
+    UpdateDefaultRowTtl(Option<Duration>),
+    UpdateSchema(Option<DbTableSchema>),
+}
+
+impl DbWalRecord {
+    pub fn serialize(&self, dest: &mut Vec<u8>) -> Result<(), DbWalError> {
+        match self {
+            DbWalRecord::InsertRow(db_row) => {
+                write_u8(dest, RECORD_INSERT_ROW)?;
+                write_bytes(dest, db_row.data.as_slice())?;
+            }
+            DbWalRecord::InsertOrReplaceRow(db_row) => {
+                write_u8(dest, RECORD_INSERT_OR_REPLACE_ROW)?;
+                write_bytes(dest, db_row.data.as_slice())?;
+            }
+            DbWalRecord::BulkInsertOrReplace {
+                partition_key,
+                db_rows,
+            } => {
+                write_u8(dest, RECORD_BULK_INSERT_OR_REPLACE)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+                write_u32(dest, db_rows.len() as u32)?;
+                for db_row in db_rows {
+                    write_bytes(dest, db_row.data.as_slice())?;
+                }
+            }
+            DbWalRecord::RemoveRow {
+                partition_key,
+                row_key,
+                delete_empty_partition,
+            } => {
+                write_u8(dest, RECORD_REMOVE_ROW)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+                write_bytes(dest, row_key.as_bytes())?;
+                write_u8(dest, *delete_empty_partition as u8)?;
+            }
+            DbWalRecord::BulkRemoveRows {
+                partition_key,
+                row_keys,
+                delete_empty_partition,
+            } => {
+                write_u8(dest, RECORD_BULK_REMOVE_ROWS)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+                write_u32(dest, row_keys.len() as u32)?;
+                for row_key in row_keys {
+                    write_bytes(dest, row_key.as_bytes())?;
+                }
+                write_u8(dest, *delete_empty_partition as u8)?;
+            }
+            DbWalRecord::RemovePartition { partition_key } => {
+                write_u8(dest, RECORD_REMOVE_PARTITION)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+            }
+            DbWalRecord::ClearTable => {
+                write_u8(dest, RECORD_CLEAR_TABLE)?;
+            }
+            DbWalRecord::UpdatePartitionExpiration {
+                partition_key,
+                expires,
+            } => {
+                write_u8(dest, RECORD_UPDATE_PARTITION_EXPIRATION)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+                write_opt_i64(dest, expires.map(|itm| itm.unix_microseconds))?;
+            }
+            DbWalRecord::UpdateRowExpiration {
+                partition_key,
+                row_key,
+                expires,
+            } => {
+                write_u8(dest, RECORD_UPDATE_ROW_EXPIRATION)?;
+                write_bytes(dest, partition_key.as_bytes())?;
+                write_bytes(dest, row_key.as_bytes())?;
+                write_opt_i64(dest, expires.map(|itm| itm.unix_microseconds))?;
+            }
+            DbWalRecord::UpdateAttributes {
+                persist,
+                max_partitions_amount,
+                max_rows_per_partition_amount,
+                tombstones_retention,
+            } => {
+                write_u8(dest, RECORD_UPDATE_ATTRIBUTES)?;
+                write_u8(dest, *persist as u8)?;
+                write_opt_u64(dest, max_partitions_amount.map(|itm| itm as u64))?;
+                write_opt_u64(dest, max_rows_per_partition_amount.map(|itm| itm as u64))?;
+                write_opt_u64(dest, tombstones_retention.map(|itm| itm.as_micros() as u64))?;
+            }
+            DbWalRecord::UpdateContentSizeBudgets {
+                max_table_content_size,
+                max_partition_content_size,
+            } => {
+                write_u8(dest, RECORD_UPDATE_CONTENT_SIZE_BUDGETS)?;
+                write_opt_u64(dest, max_table_content_size.map(|itm| itm as u64))?;
+                write_opt_u64(dest, max_partition_content_size.map(|itm| itm as u64))?;
+            }
+            DbWalRecord::UpdateSlidingExpiration(sliding_expiration) => {
+                write_u8(dest, RECORD_UPDATE_SLIDING_EXPIRATION)?;
+                match sliding_expiration {
+                    Some(sliding_expiration) => {
+                        write_u8(dest, 1)?;
+                        write_u64(dest, sliding_expiration.duration.as_micros() as u64)?;
+                        write_u8(dest, sliding_expiration.extend_partition as u8)?;
+                    }
+                    None => write_u8(dest, 0)?,
+                }
+            }
+            DbWalRecord::UpdateDefaultRowTtl(default_row_ttl) => {
+                write_u8(dest, RECORD_UPDATE_DEFAULT_ROW_TTL)?;
+                write_opt_u64(dest, default_row_ttl.map(|itm| itm.as_micros() as u64))?;
+            }
+            DbWalRecord::UpdateSchema(schema) => {
+                write_u8(dest, RECORD_UPDATE_SCHEMA)?;
+                write_opt_schema(dest, schema.as_ref())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn deserialize(mut src: &[u8]) -> Result<Self, DbWalError> {
+        let src = &mut src;
+
+        let result = match read_u8(src)? {
+            RECORD_INSERT_ROW => DbWalRecord::InsertRow(read_db_row(src)?),
+            RECORD_INSERT_OR_REPLACE_ROW => DbWalRecord::InsertOrReplaceRow(read_db_row(src)?),
+            RECORD_BULK_INSERT_OR_REPLACE => {
+                let partition_key = read_string(src)?;
+                let amount = read_u32(src)?;
+                let mut db_rows = Vec::new();
+                for _ in 0..amount {
+                    db_rows.push(read_db_row(src)?);
+                }
+
+                DbWalRecord::BulkInsertOrReplace {
+                    partition_key,
+                    db_rows,
+                }
+            }
+            RECORD_REMOVE_ROW => DbWalRecord::RemoveRow {
+                partition_key: read_string(src)?,
+                row_key: read_string(src)?,
+                delete_empty_partition: read_u8(src)? != 0,
+            },
+            RECORD_BULK_REMOVE_ROWS => {
+                let partition_key = read_string(src)?;
+                let amount = read_u32(src)?;
+                let mut row_keys = Vec::new();
+                for _ in 0..amount {
+                    row_keys.push(read_string(src)?);
+                }
+
+                DbWalRecord::BulkRemoveRows {
+                    partition_key,
+                    row_keys,
+                    delete_empty_partition: read_u8(src)? != 0,
+                }
+            }
+            RECORD_REMOVE_PARTITION => DbWalRecord::RemovePartition {
+                partition_key: read_string(src)?,
+            },
+            RECORD_CLEAR_TABLE => DbWalRecord::ClearTable,
+            RECORD_UPDATE_PARTITION_EXPIRATION => DbWalRecord::UpdatePartitionExpiration {
+                partition_key: read_string(src)?,
+                expires: read_opt_i64(src)?.map(DateTimeAsMicroseconds::new),
+            },
+            RECORD_UPDATE_ROW_EXPIRATION => DbWalRecord::UpdateRowExpiration {
+                partition_key: read_string(src)?,
+                row_key: read_string(src)?,
+                expires: read_opt_i64(src)?.map(DateTimeAsMicroseconds::new),
+            },
+            RECORD_UPDATE_ATTRIBUTES => DbWalRecord::UpdateAttributes {
+                persist: read_u8(src)? != 0,
+                max_partitions_amount: read_opt_u64(src)?.map(|itm| itm as usize),
+                max_rows_per_partition_amount: read_opt_u64(src)?.map(|itm| itm as usize),
+                tombstones_retention: read_opt_u64(src)?.map(Duration::from_micros),
+            },
+            RECORD_UPDATE_CONTENT_SIZE_BUDGETS => DbWalRecord::UpdateContentSizeBudgets {
+                max_table_content_size: read_opt_u64(src)?.map(|itm| itm as usize),
+                max_partition_content_size: read_opt_u64(src)?.map(|itm| itm as usize),
+            },
+            RECORD_UPDATE_SLIDING_EXPIRATION => {
+                let sliding_expiration = if read_u8(src)? == 0 {
+                    None
+                } else {
+                    Some(SlidingExpiration {
+                        duration: Duration::from_micros(read_u64(src)?),
+                        extend_partition: read_u8(src)? != 0,
+                    })
+                };
+
+                DbWalRecord::UpdateSlidingExpiration(sliding_expiration)
+            }
+            RECORD_UPDATE_DEFAULT_ROW_TTL => {
+                DbWalRecord::UpdateDefaultRowTtl(read_opt_u64(src)?.map(Duration::from_micros))
+            }
+            RECORD_UPDATE_SCHEMA => DbWalRecord::UpdateSchema(read_opt_schema(src)?),
+            record_type => return Err(DbWalError::InvalidRecordType(record_type)),
+        };
+
+        if !src.is_empty() {
+            return Err(DbWalError::InvalidRecord);
+        }
+
+        Ok(result)
+    }
+
+    /// Rows are inserted through the regular insert operations, so they are checked against the schema
+    pub fn apply(
+        &self,
+        db_table: &mut DbTable,
+        moment: DateTimeAsMicroseconds,
+    ) -> Result<(), DbWalError> {
+        match self {
+            DbWalRecord::InsertRow(db_row) => {
+                db_table.insert_row(db_row, Some(moment))?;
+            }
+            DbWalRecord::InsertOrReplaceRow(db_row) => {
+                db_table.insert_or_replace_row(db_row, Some(moment))?;
+            }
+            DbWalRecord::BulkInsertOrReplace {
+                partition_key,
+                db_rows,
+            } => {
+                db_table.bulk_insert_or_replace(partition_key, db_rows, Some(moment))?;
+            }
+            DbWalRecord::RemoveRow {
+                partition_key,
+                row_key,
+                delete_empty_partition,
+            } => {
+                db_table.remove_row(
+                    partition_key,
+                    row_key,
+                    *delete_empty_partition,
+                    Some(moment),
+                );
+            }
+            DbWalRecord::BulkRemoveRows {
+                partition_key,
+                row_keys,
+                delete_empty_partition,
+            } => {
+                db_table.bulk_remove_rows(
+                    partition_key,
+                    row_keys.iter(),
+                    *delete_empty_partition,
+                    Some(moment),
+                );
+            }
+            DbWalRecord::RemovePartition { partition_key } => {
+                db_table.remove_partition(partition_key, Some(moment));
+            }
+            DbWalRecord::ClearTable => {
+                db_table.clear_table();
+            }
+            DbWalRecord::UpdatePartitionExpiration {
+                partition_key,
+                expires,
+            } => {
+                db_table.update_partition_expiration(partition_key, *expires);
+            }
+            DbWalRecord::UpdateRowExpiration {
+                partition_key,
+                row_key,
+                expires,
+            } => {
+                db_table.update_row_expiration(partition_key, row_key, *expires, Some(moment));
+            }
+            DbWalRecord::UpdateAttributes {
+                persist,
+                max_partitions_amount,
+                max_rows_per_partition_amount,
+                tombstones_retention,
+            } => {
+                db_table.attributes.update(
+                    *persist,
+                    *max_partitions_amount,
+                    *max_rows_per_partition_amount,
+                );
+                db_table.attributes.tombstones_retention = *tombstones_retention;
+            }
+            DbWalRecord::UpdateContentSizeBudgets {
+                max_table_content_size,
+                max_partition_content_size,
+            } => {
+                db_table.attributes.max_table_content_size = *max_table_content_size;
+                db_table.attributes.max_partition_content_size = *max_partition_content_size;
+            }
+            DbWalRecord::UpdateSlidingExpiration(sliding_expiration) => {
+                db_table.attributes.sliding_expiration = *sliding_expiration;
+            }
+            DbWalRecord::UpdateDefaultRowTtl(default_row_ttl) => {
+                db_table.attributes.default_row_ttl = *default_row_ttl;
+            }
+            DbWalRecord::UpdateSchema(schema) => {
+                db_table.attributes.schema = schema.clone();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_db_row(src: &mut &[u8]) -> Result<Arc<DbRow>, DbWalError> {
+    let data = read_bytes(src)?;
+    let db_json_entity = DbJsonEntity::parse(data.as_slice())?;
+    Ok(Arc::new(db_json_entity.restore_db_row()))
+}
@@ -0,0 +1,389 @@
+use std::io::Read;
+
+use crate::{db::DbTable, db_snapshot::DbTableSnapshotReader};
+
+use super::{DbWalError, DbWalReader};
+
+#[derive(Debug)]
+pub struct DbWalReplayResult {
+    pub records_applied: usize,
+    pub torn_tail: bool,
+    /// Log has to be truncated to this length before new records are appended to it.
+    /// Zero means that even the header has not been written completely
+    pub valid_len: u64,
+}
+
+pub fn replay_wal<R: Read>(
+    db_table: &mut DbTable,
+    wal: R,
+) -> Result<DbWalReplayResult, DbWalError> {
+    let mut reader = DbWalReader::new(wal);
+    let mut records_applied = 0;
+
+    while let Some(entry) = reader.read_next()? {
+        entry.record.apply(db_table, entry.moment)?;
+        records_applied += 1;
+    }
+
+    Ok(DbWalReplayResult {
+        records_applied,
+        torn_tail: reader.has_torn_tail(),
+        valid_len: reader.get_valid_len(),
+    })
+}
+
+/// WAL has to be started from scratch every time snapshot is written,
+/// so the WAL contains only the changes made after the snapshot
+pub fn restore_table<S: Read, W: Read>(
+    snapshot: S,
+    wal: W,
+) -> Result<(DbTable, DbWalReplayResult), DbWalError> {
+    let mut db_table = DbTableSnapshotReader::new(snapshot).read_table()?;
+    let replay_result = replay_wal(&mut db_table, wal)?;
+    Ok((db_table, replay_result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use crate::{
+        db::{DbRow, DbTable},
+        db_snapshot::DbTableSnapshotWriter,
+        db_wal::{DbWalError, DbWalRecord, DbWalWriter},
+        test_utils::{create_db_row_with_fields, create_db_table},
+        validations::{DbTableSchema, SchemaField, SchemaFieldType},
+    };
+
+    fn create_db_row_with_value(partition_key: &str, row_key: &str, value: &str) -> Arc<DbRow> {
+        create_db_row_with_fields(partition_key, row_key, &format!(r#""Value":"{}""#, value))
+    }
+
+    fn get_content(db_table: &DbTable) -> Vec<(String, String, Vec<u8>)> {
+        db_table
+            .get_all_rows()
+            .into_iter()
+            .map(|itm| {
+                (
+                    itm.partition_key.to_string(),
+                    itm.row_key.to_string(),
+                    itm.data.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_and_wal_replay() {
+        let mut db_table = create_db_table();
+
+        db_table
+            .insert_row(&create_db_row_with_value("pk1", "rk1", "1"), None)
+            .unwrap();
+        db_table
+            .insert_row(&create_db_row_with_value("pk2", "rk1", "1"), None)
+            .unwrap();
+
+        let snapshot = DbTableSnapshotWriter::new(Vec::new())
+            .write_table(&db_table)
+            .unwrap();
+
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        let records = vec![
+            DbWalRecord::InsertOrReplaceRow(create_db_row_with_value("pk1", "rk1", "2")),
+            DbWalRecord::BulkInsertOrReplace {
+                partition_key: "pk3".to_string(),
+                db_rows: vec![
+                    create_db_row_with_value("pk3", "rk1", "1"),
+                    create_db_row_with_value("pk3", "rk2", "1"),
+                ],
+            },
+            DbWalRecord::RemovePartition {
+                partition_key: "pk2".to_string(),
+            },
+            DbWalRecord::BulkRemoveRows {
+                partition_key: "pk3".to_string(),
+                row_keys: vec!["rk2".to_string()],
+                delete_empty_partition: true,
+            },
+            DbWalRecord::UpdateAttributes {
+                persist: false,
+                max_partitions_amount: Some(5),
+                max_rows_per_partition_amount: None,
+                tombstones_retention: Some(Duration::from_secs(1)),
+            },
+            DbWalRecord::UpdateContentSizeBudgets {
+                max_table_content_size: Some(1024 * 1024),
+                max_partition_content_size: None,
+            },
+            DbWalRecord::UpdateDefaultRowTtl(Some(Duration::from_secs(60))),
+            DbWalRecord::UpdateSchema(Some(DbTableSchema {
+                fields: vec![SchemaField {
+                    name: "Value".to_string(),
+                    field_type: SchemaFieldType::Number,
+                    required: true,
+                }],
+                max_row_size: None,
+            })),
+        ];
+
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+
+        for record in &records {
+            record.apply(&mut db_table, moment).unwrap();
+            wal_writer.append(moment, record).unwrap();
+        }
+
+        let mut wal = wal_writer.into_inner();
+
+        // Write interrupted by the crash
+        let wal_len = wal.len();
+        let mut wal_writer = DbWalWriter::from_existing(wal);
+        wal_writer.append(moment, &DbWalRecord::ClearTable).unwrap();
+        wal = wal_writer.into_inner();
+        wal.truncate(wal_len + 5);
+
+        let (restored, replay_result) =
+            super::restore_table(snapshot.as_slice(), wal.as_slice()).unwrap();
+
+        assert_eq!(records.len(), replay_result.records_applied);
+        assert!(replay_result.torn_tail);
+
+        assert_eq!(get_content(&db_table), get_content(&restored));
+        assert!(!restored.attributes.persist);
+        assert_eq!(Some(5), restored.attributes.max_partitions_amount);
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            restored.attributes.tombstones_retention
+        );
+        assert_eq!(
+            Some(1024 * 1024),
+            restored.attributes.max_table_content_size
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            restored.attributes.default_row_ttl
+        );
+        assert_eq!(db_table.attributes.schema, restored.attributes.schema);
+    }
+
+    #[test]
+    fn test_corrupted_last_record() {
+        let mut db_table = create_db_table();
+
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk1", "1")),
+            )
+            .unwrap();
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk2", "1")),
+            )
+            .unwrap();
+
+        let mut wal = wal_writer.into_inner();
+        let last_index = wal.len() - 1;
+        wal[last_index] ^= 0xFF;
+
+        let replay_result = super::replay_wal(&mut db_table, wal.as_slice()).unwrap();
+
+        assert_eq!(1, replay_result.records_applied);
+        assert!(replay_result.torn_tail);
+        assert_eq!(1, db_table.get_rows_amount());
+    }
+
+    #[test]
+    fn test_replay_row_expiration_update() {
+        let mut db_table = create_db_table();
+
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        let records = vec![
+            DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk1", "1")),
+            DbWalRecord::UpdateRowExpiration {
+                partition_key: "pk1".to_string(),
+                row_key: "rk1".to_string(),
+                expires: Some(DateTimeAsMicroseconds::new(5_000_000)),
+            },
+        ];
+
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+
+        for record in &records {
+            wal_writer.append(moment, record).unwrap();
+        }
+
+        let wal = wal_writer.into_inner();
+
+        super::replay_wal(&mut db_table, wal.as_slice()).unwrap();
+
+        let db_row = db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk1")
+            .unwrap();
+        assert_eq!(db_row.data.len(), db_table.get_table_size());
+        assert_eq!(2, db_table.get_sequence());
+        assert!(db_table.partitions.has_dirty_partitions());
+
+        // Content size has to match the rewritten row, otherwise removal underflows it
+        db_table.remove_row(&"pk1".to_string(), "rk1", false, None);
+        assert_eq!(0, db_table.get_table_size());
+    }
+
+    #[test]
+    fn test_replay_checks_schema() {
+        let mut db_table = create_db_table();
+
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        // Row written by the server is bigger than the client JSON because of the TimeStamp.
+        // Size belongs to the client JSON, so it's not checked again
+        let client_json = r#"{"PartitionKey":"pk1","RowKey":"rk1","Value":"1"}"#;
+
+        let records = vec![
+            DbWalRecord::UpdateSchema(Some(DbTableSchema {
+                fields: vec![SchemaField {
+                    name: "Value".to_string(),
+                    field_type: SchemaFieldType::String,
+                    required: true,
+                }],
+                max_row_size: Some(client_json.len()),
+            })),
+            DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk1", "1")),
+        ];
+
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+
+        for record in &records {
+            wal_writer.append(moment, record).unwrap();
+        }
+
+        let wal = wal_writer.into_inner();
+
+        let replay_result = super::replay_wal(&mut db_table, wal.as_slice()).unwrap();
+        assert_eq!(2, replay_result.records_applied);
+        assert_eq!(1, db_table.get_rows_amount());
+
+        let mut wal_writer = DbWalWriter::from_existing(wal);
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_fields("pk1", "rk2", r#""Value":12"#)),
+            )
+            .unwrap();
+        let wal = wal_writer.into_inner();
+
+        let mut db_table = create_db_table();
+
+        let result = super::replay_wal(&mut db_table, wal.as_slice());
+        assert!(matches!(result, Err(DbWalError::SchemaValidationError(_))));
+        assert!(db_table
+            .get_partition("pk1")
+            .unwrap()
+            .get_row("rk2")
+            .is_none());
+    }
+
+    #[test]
+    fn test_append_after_torn_tail() {
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        let mut wal_writer = DbWalWriter::new(Vec::new()).unwrap();
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk1", "1")),
+            )
+            .unwrap();
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk2", "1")),
+            )
+            .unwrap();
+
+        let mut wal = wal_writer.into_inner();
+        wal.truncate(wal.len() - 3);
+
+        let mut db_table = create_db_table();
+        let replay_result = super::replay_wal(&mut db_table, wal.as_slice()).unwrap();
+        assert!(replay_result.torn_tail);
+        assert_eq!(1, replay_result.records_applied);
+
+        // Restart after the crash: torn tail is cut off and the log is continued
+        wal.truncate(replay_result.valid_len as usize);
+        let mut wal_writer = DbWalWriter::from_existing(wal);
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk3", "1")),
+            )
+            .unwrap();
+        let wal = wal_writer.into_inner();
+
+        let mut db_table = create_db_table();
+        let replay_result = super::replay_wal(&mut db_table, wal.as_slice()).unwrap();
+
+        assert!(!replay_result.torn_tail);
+        assert_eq!(2, replay_result.records_applied);
+        assert_eq!(wal.len() as u64, replay_result.valid_len);
+        assert_eq!(2, db_table.get_rows_amount());
+    }
+
+    #[test]
+    fn test_continue_file() {
+        let path = std::env::temp_dir().join(format!(
+            "my-no-sql-core-wal-{}-{}",
+            std::process::id(),
+            DateTimeAsMicroseconds::now().unix_microseconds
+        ));
+
+        let moment = DateTimeAsMicroseconds::new(1_000_000);
+
+        let mut wal_writer = DbWalWriter::new(std::fs::File::create(&path).unwrap()).unwrap();
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk1", "1")),
+            )
+            .unwrap();
+        wal_writer.flush().unwrap();
+
+        let file_len = std::fs::metadata(&path).unwrap().len();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file_len + 5).unwrap();
+
+        let mut db_table = create_db_table();
+        let replay_result =
+            super::replay_wal(&mut db_table, std::fs::File::open(&path).unwrap()).unwrap();
+        assert!(replay_result.torn_tail);
+
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        let mut wal_writer = DbWalWriter::continue_file(file, &replay_result).unwrap();
+        wal_writer
+            .append(
+                moment,
+                &DbWalRecord::InsertRow(create_db_row_with_value("pk1", "rk2", "1")),
+            )
+            .unwrap();
+        wal_writer.flush().unwrap();
+
+        let mut db_table = create_db_table();
+        let replay_result =
+            super::replay_wal(&mut db_table, std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!replay_result.torn_tail);
+        assert_eq!(2, replay_result.records_applied);
+    }
+}
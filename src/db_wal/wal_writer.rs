@@ -0,0 +1,91 @@
+use std::io::Write;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::{crc32::crc32, DbWalError, DbWalRecord};
+
+// Header: WAL_MAGIC, version: u32
+// Frame: payload_len: u32, crc32 of payload: u32, payload
+// Payload: moment: i64, record
+pub const WAL_MAGIC: &[u8; 4] = b"MNWL";
+pub const WAL_VERSION: u32 = 1;
+
+/// Records are never that large, so a bigger length can only be read from a corrupted log
+pub const MAX_WAL_RECORD_SIZE: usize = 64 * 1024 * 1024;
+
+pub struct DbWalWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> DbWalWriter<W> {
+    /// Starts the new log
+    pub fn new(mut writer: W) -> Result<Self, DbWalError> {
+        writer.write_all(WAL_MAGIC)?;
+        writer.write_all(&WAL_VERSION.to_le_bytes())?;
+
+        Ok(Self::from_existing(writer))
+    }
+
+    /// Continues the log which already has the header. Writer has to be positioned at the
+    /// valid_len of the replay result with everything after it truncated - see continue_file
+    pub fn from_existing(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn append(
+        &mut self,
+        moment: DateTimeAsMicroseconds,
+        record: &DbWalRecord,
+    ) -> Result<(), DbWalError> {
+        self.buffer.clear();
+        self.buffer
+            .extend_from_slice(&moment.unix_microseconds.to_le_bytes());
+        record.serialize(&mut self.buffer)?;
+
+        if self.buffer.len() > MAX_WAL_RECORD_SIZE {
+            return Err(DbWalError::RecordIsTooLarge(self.buffer.len()));
+        }
+
+        let mut header = [0u8; 8];
+        header[..4].copy_from_slice(&(self.buffer.len() as u32).to_le_bytes());
+        header[4..].copy_from_slice(&crc32(self.buffer.as_slice()).to_le_bytes());
+
+        self.writer.write_all(&header)?;
+        self.writer.write_all(self.buffer.as_slice())?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), DbWalError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl DbWalWriter<std::fs::File> {
+    /// Continues the log file after the replay. Torn tail is cut off,
+    /// so the records appended now follow the last valid record
+    pub fn continue_file(
+        mut file: std::fs::File,
+        replay_result: &super::DbWalReplayResult,
+    ) -> Result<Self, DbWalError> {
+        use std::io::{Seek, SeekFrom};
+
+        file.set_len(replay_result.valid_len)?;
+        file.seek(SeekFrom::Start(replay_result.valid_len))?;
+
+        if replay_result.valid_len == 0 {
+            return Self::new(file);
+        }
+
+        Ok(Self::from_existing(file))
+    }
+}
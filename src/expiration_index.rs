@@ -19,6 +19,7 @@ impl<T: Clone + ExpirationItemsAreSame<T>> ExpirationIndex<T> {
         }
     }
 
+    /// Adding the same item with the same expiration moment twice keeps one copy of it
     pub fn add(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) {
         if expiration_moment.is_none() {
             return;
@@ -26,30 +27,43 @@ impl<T: Clone + ExpirationItemsAreSame<T>> ExpirationIndex<T> {
 
         let expire_moment = expiration_moment.unwrap().unix_microseconds;
 
-        match self.index.get_mut(&expire_moment) {
-            Some(items) => {
-                items.push(item.clone());
-            }
-            None => {
-                self.index.insert(expire_moment, vec![item.clone()]);
-            }
+        let items = self.index.entry(expire_moment).or_default();
+
+        if let Some(existing) = items.iter_mut().find(|f| item.are_same(f)) {
+            *existing = item.clone();
+            return;
         }
 
+        items.push(item.clone());
         self.amount += 1;
     }
 
-    pub fn remove(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) {
+    /// Returns true if the item was found and removed
+    pub fn remove(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) -> bool {
         if expiration_moment.is_none() {
-            return;
+            return false;
         }
 
         let expire_moment = expiration_moment.unwrap().unix_microseconds;
 
-        if let Some(items) = self.index.get_mut(&expire_moment) {
-            items.retain(|f| !item.are_same(f));
+        let items = match self.index.get_mut(&expire_moment) {
+            Some(items) => items,
+            None => return false,
+        };
+
+        let index = match items.iter().position(|f| item.are_same(f)) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        items.remove(index);
+
+        if items.is_empty() {
+            self.index.remove(&expire_moment);
         }
 
         self.amount -= 1;
+        true
     }
 
     pub fn update(
@@ -92,16 +106,68 @@ impl<T: Clone + ExpirationItemsAreSame<T>> ExpirationIndex<T> {
         result.get_result()
     }
 
+    /// Removes the expired items from the index and returns them
+    pub fn drain_items_to_expire(&mut self, now: DateTimeAsMicroseconds) -> Option<Vec<T>> {
+        let mut result = LazyVec::new();
+
+        while let Some(entry) = self.index.first_entry() {
+            if *entry.key() > now.unix_microseconds {
+                break;
+            }
+
+            for itm in entry.remove() {
+                self.amount -= 1;
+                result.add(itm);
+            }
+        }
+
+        result.get_result()
+    }
+
     pub fn has_data_with_expiration_moment(&self, expiration_moment: i64) -> bool {
         self.index.contains_key(&expiration_moment)
     }
 
+    pub fn get_earliest_expiration_moment(&self) -> Option<DateTimeAsMicroseconds> {
+        let (expiration_moment, _) = self.index.first_key_value()?;
+        Some(DateTimeAsMicroseconds::new(*expiration_moment))
+    }
+
     pub fn len(&self) -> usize {
         self.amount
     }
 
     pub fn clear(&mut self) {
         self.index.clear();
+        self.amount = 0;
+    }
+
+    /// Panics if the internal state of the index is inconsistent
+    pub fn debug_validate(&self) {
+        let mut amount = 0;
+
+        for (expiration_moment, items) in &self.index {
+            assert!(
+                !items.is_empty(),
+                "Empty bucket with expiration moment {}",
+                expiration_moment
+            );
+
+            for (i, item) in items.iter().enumerate() {
+                assert!(
+                    !items[i + 1..].iter().any(|other| item.are_same(other)),
+                    "Duplicate item with expiration moment {}",
+                    expiration_moment
+                );
+            }
+
+            amount += items.len();
+        }
+
+        assert_eq!(
+            amount, self.amount,
+            "Amount of items does not match the index"
+        );
     }
 }
 
@@ -110,3 +176,144 @@ impl ExpirationItemsAreSame<String> for String {
         self == other_one
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use super::ExpirationIndex;
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Add(Option<i64>, String),
+        Remove(Option<i64>, String),
+        Update(Option<i64>, Option<i64>, String),
+        Drain(i64),
+        Clear,
+    }
+
+    fn to_moment(value: Option<i64>) -> Option<DateTimeAsMicroseconds> {
+        value.map(DateTimeAsMicroseconds::new)
+    }
+
+    fn operation_strategy() -> impl Strategy<Value = Operation> {
+        let moment = proptest::option::of(0i64..5);
+        let item = "[a-d]";
+
+        prop_oneof![
+            4 => (moment.clone(), item).prop_map(|(moment, item)| Operation::Add(moment, item)),
+            3 => (moment.clone(), item)
+                .prop_map(|(moment, item)| Operation::Remove(moment, item)),
+            3 => (moment.clone(), moment, item)
+                .prop_map(|(old, new, item)| Operation::Update(old, new, item)),
+            1 => (-1i64..6).prop_map(Operation::Drain),
+            1 => Just(Operation::Clear),
+        ]
+    }
+
+    fn remove_from_model(model: &mut BTreeSet<(i64, String)>, moment: Option<i64>, item: &str) {
+        if let Some(moment) = moment {
+            model.remove(&(moment, item.to_string()));
+        }
+    }
+
+    fn add_to_model(model: &mut BTreeSet<(i64, String)>, moment: Option<i64>, item: &str) {
+        if let Some(moment) = moment {
+            model.insert((moment, item.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_remove_of_absent_item() {
+        let mut index = ExpirationIndex::new();
+
+        index.add(Some(DateTimeAsMicroseconds::new(1)), &"a".to_string());
+
+        assert!(!index.remove(Some(DateTimeAsMicroseconds::new(1)), &"b".to_string()));
+        assert!(!index.remove(Some(DateTimeAsMicroseconds::new(2)), &"a".to_string()));
+        assert_eq!(1, index.len());
+
+        assert!(index.remove(Some(DateTimeAsMicroseconds::new(1)), &"a".to_string()));
+        assert_eq!(0, index.len());
+        assert!(!index.has_data_with_expiration_moment(1));
+
+        index.debug_validate();
+    }
+
+    proptest! {
+        #[test]
+        fn test_index_matches_model(operations in proptest::collection::vec(operation_strategy(), 0..64)) {
+            let mut index = ExpirationIndex::new();
+            let mut model = BTreeSet::new();
+
+            for operation in operations {
+                match &operation {
+                    Operation::Add(moment, item) => {
+                        index.add(to_moment(*moment), item);
+                        add_to_model(&mut model, *moment, item);
+                    }
+                    Operation::Remove(moment, item) => {
+                        let removed = index.remove(to_moment(*moment), item);
+                        let expected = moment.is_some_and(|moment| model.contains(&(moment, item.clone())));
+                        prop_assert_eq!(expected, removed);
+                        remove_from_model(&mut model, *moment, item);
+                    }
+                    Operation::Update(old, new, item) => {
+                        index.update(to_moment(*old), to_moment(*new), item);
+                        remove_from_model(&mut model, *old, item);
+                        add_to_model(&mut model, *new, item);
+                    }
+                    Operation::Drain(now) => {
+                        let mut drained = index
+                            .drain_items_to_expire(DateTimeAsMicroseconds::new(*now))
+                            .unwrap_or_default();
+                        drained.sort();
+
+                        let mut expected: Vec<String> = model
+                            .iter()
+                            .filter(|(moment, _)| *moment <= *now)
+                            .map(|(_, item)| item.clone())
+                            .collect();
+                        expected.sort();
+
+                        model.retain(|(moment, _)| *moment > *now);
+                        prop_assert_eq!(expected, drained);
+                    }
+                    Operation::Clear => {
+                        index.clear();
+                        model.clear();
+                    }
+                }
+
+                index.debug_validate();
+                prop_assert_eq!(model.len(), index.len());
+
+                for now in -1..6 {
+                    let mut expected: Vec<String> = model
+                        .iter()
+                        .filter(|(moment, _)| *moment <= now)
+                        .map(|(_, item)| item.clone())
+                        .collect();
+                    expected.sort();
+
+                    let mut actual = index
+                        .get_items_to_expire_cloned(DateTimeAsMicroseconds::new(now))
+                        .unwrap_or_default();
+                    actual.sort();
+
+                    prop_assert_eq!(expected, actual);
+                }
+
+                for moment in 0..5 {
+                    prop_assert_eq!(
+                        model.iter().any(|(itm, _)| *itm == moment),
+                        index.has_data_with_expiration_moment(moment)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{BucketedExpirationIndex, ExpirationIndex, ExpirationItemKey, ExpirationItemsAreSame};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpirationIndexKind {
+    /// Bucket per expiration moment. Suits tables with few distinct expiration moments
+    #[default]
+    BTree,
+    /// Flat buckets of `resolution` width, not a hierarchical timing wheel.
+    /// Suits tables with many rows with unique expiration moments
+    Bucketed { resolution: Duration },
+}
+
+pub enum AnyExpirationIndex<T: Clone + ExpirationItemsAreSame<T> + ExpirationItemKey> {
+    BTree(ExpirationIndex<T>),
+    Bucketed(BucketedExpirationIndex<T>),
+}
+
+impl<T: Clone + ExpirationItemsAreSame<T> + ExpirationItemKey> AnyExpirationIndex<T> {
+    pub fn new(kind: ExpirationIndexKind) -> Self {
+        match kind {
+            ExpirationIndexKind::BTree => Self::BTree(ExpirationIndex::new()),
+            ExpirationIndexKind::Bucketed { resolution } => {
+                Self::Bucketed(BucketedExpirationIndex::new(resolution))
+            }
+        }
+    }
+
+    pub fn add(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) {
+        match self {
+            Self::BTree(index) => index.add(expiration_moment, item),
+            Self::Bucketed(index) => index.add(expiration_moment, item),
+        }
+    }
+
+    pub fn remove(&mut self, expiration_moment: Option<DateTimeAsMicroseconds>, item: &T) -> bool {
+        match self {
+            Self::BTree(index) => index.remove(expiration_moment, item),
+            Self::Bucketed(index) => index.remove(expiration_moment, item),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        old_expiration_moment: Option<DateTimeAsMicroseconds>,
+        new_expiration_moment: Option<DateTimeAsMicroseconds>,
+        item: &T,
+    ) {
+        match self {
+            Self::BTree(index) => index.update(old_expiration_moment, new_expiration_moment, item),
+            Self::Bucketed(index) => {
+                index.update(old_expiration_moment, new_expiration_moment, item)
+            }
+        }
+    }
+
+    pub fn get_items_to_expire(&self, now: DateTimeAsMicroseconds) -> Option<Vec<&T>> {
+        match self {
+            Self::BTree(index) => index.get_items_to_expire(now),
+            Self::Bucketed(index) => index.get_items_to_expire(now),
+        }
+    }
+
+    pub fn get_items_to_expire_cloned(&self, now: DateTimeAsMicroseconds) -> Option<Vec<T>> {
+        match self {
+            Self::BTree(index) => index.get_items_to_expire_cloned(now),
+            Self::Bucketed(index) => index.get_items_to_expire_cloned(now),
+        }
+    }
+
+    pub fn drain_items_to_expire(&mut self, now: DateTimeAsMicroseconds) -> Option<Vec<T>> {
+        match self {
+            Self::BTree(index) => index.drain_items_to_expire(now),
+            Self::Bucketed(index) => index.drain_items_to_expire(now),
+        }
+    }
+
+    pub fn has_data_with_expiration_moment(&self, expiration_moment: i64) -> bool {
+        match self {
+            Self::BTree(index) => index.has_data_with_expiration_moment(expiration_moment),
+            Self::Bucketed(index) => index.has_data_with_expiration_moment(expiration_moment),
+        }
+    }
+
+    pub fn get_earliest_expiration_moment(&self) -> Option<DateTimeAsMicroseconds> {
+        match self {
+            Self::BTree(index) => index.get_earliest_expiration_moment(),
+            Self::Bucketed(index) => index.get_earliest_expiration_moment(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::BTree(index) => index.len(),
+            Self::Bucketed(index) => index.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::BTree(index) => index.clear(),
+            Self::Bucketed(index) => index.clear(),
+        }
+    }
+
+    pub fn debug_validate(&self) {
+        match self {
+            Self::BTree(index) => index.debug_validate(),
+            Self::Bucketed(index) => index.debug_validate(),
+        }
+    }
+}
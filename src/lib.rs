@@ -1,6 +1,17 @@
+mod bucketed_expiration_index;
 pub mod db;
+pub mod db_filter;
 pub mod db_json_entity;
+#[cfg(feature = "master-node")]
+pub mod db_snapshot;
+#[cfg(feature = "master-node")]
+pub mod db_wal;
 mod expiration_index;
+mod expiration_index_kind;
 
+#[cfg(test)]
+mod test_utils;
 pub mod validations;
+pub use bucketed_expiration_index::*;
 pub use expiration_index::*;
+pub use expiration_index_kind::*;
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+#[cfg(feature = "master-node")]
+use crate::db::{DbTable, DbTableAttributes};
+use crate::{
+    db::DbRow,
+    db_json_entity::{DbJsonEntity, JsonTimeStamp},
+    validations::{DbTableSchema, SchemaFieldType},
+};
+
+pub fn create_db_row_from_json(json: &str, time_stamp: &JsonTimeStamp) -> Arc<DbRow> {
+    let db_json_entity = DbJsonEntity::parse(json.as_bytes()).unwrap();
+    Arc::new(db_json_entity.new_db_row(time_stamp))
+}
+
+/// Fields are written into the JSON as they are. Example: `"Amount":5`
+#[cfg(feature = "master-node")]
+pub fn create_db_row_with_fields(partition_key: &str, row_key: &str, fields: &str) -> Arc<DbRow> {
+    let json = format!(
+        r#"{{"PartitionKey":"{}","RowKey":"{}",{}}}"#,
+        partition_key, row_key, fields
+    );
+    create_db_row_from_json(&json, &JsonTimeStamp::now())
+}
+
+pub fn create_db_row_at(
+    partition_key: &str,
+    row_key: &str,
+    time_stamp: &JsonTimeStamp,
+) -> Arc<DbRow> {
+    let json = format!(
+        r#"{{"PartitionKey":"{}","RowKey":"{}"}}"#,
+        partition_key, row_key
+    );
+    create_db_row_from_json(&json, time_stamp)
+}
+
+pub fn create_db_row(partition_key: &str, row_key: &str) -> Arc<DbRow> {
+    create_db_row_at(partition_key, row_key, &JsonTimeStamp::now())
+}
+
+pub fn create_schema() -> DbTableSchema {
+    let mut schema = DbTableSchema::new();
+    schema.add_field("Name", SchemaFieldType::String, true);
+    schema.add_field("Amount", SchemaFieldType::Number, true);
+    schema.add_field("Active", SchemaFieldType::Bool, false);
+    schema.add_field("Tags", SchemaFieldType::Array, false);
+    schema.add_field("Created", SchemaFieldType::DateTime, false);
+    schema
+}
+
+#[cfg(feature = "master-node")]
+pub fn create_db_table() -> DbTable {
+    DbTable::new(
+        "test-table".to_string(),
+        DbTableAttributes::create_default(),
+    )
+}
+
+#[cfg(feature = "master-node")]
+pub fn insert_test_row(db_table: &mut DbTable, partition_key: &str, row_key: &str) {
+    db_table
+        .insert_or_replace_row(&create_db_row(partition_key, row_key), None)
+        .unwrap();
+}
@@ -0,0 +1,226 @@
+use my_json::json_reader::JsonFirstLineReader;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::db_json_entity::JsonFieldValue;
+
+use super::SchemaValidationError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFieldType {
+    String,
+    Number,
+    Bool,
+    Object,
+    Array,
+    /// String with the date and time
+    DateTime,
+}
+
+impl SchemaFieldType {
+    fn matches(&self, value: &JsonFieldValue) -> bool {
+        match (self, value) {
+            (Self::String, JsonFieldValue::String(_)) => true,
+            (Self::Number, JsonFieldValue::Number(_)) => true,
+            (Self::Bool, JsonFieldValue::Bool(_)) => true,
+            (Self::Object, JsonFieldValue::Object(_)) => true,
+            (Self::Array, JsonFieldValue::Array(_)) => true,
+            (Self::DateTime, JsonFieldValue::String(_)) => match value.as_string() {
+                Some(value) => DateTimeAsMicroseconds::from_str(value.as_ref()).is_some(),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaField {
+    pub name: String,
+    pub field_type: SchemaFieldType,
+    pub required: bool,
+}
+
+/// Fields which are not declared in the schema are not checked
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbTableSchema {
+    pub fields: Vec<SchemaField>,
+    /// Max size of the row JSON in bytes
+    pub max_row_size: Option<usize>,
+}
+
+impl DbTableSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_field(&mut self, name: &str, field_type: SchemaFieldType, required: bool) {
+        self.fields.push(SchemaField {
+            name: name.to_string(),
+            field_type,
+            required,
+        });
+    }
+
+    /// Validates the JSON the client has sent
+    pub fn validate(&self, raw: &[u8]) -> Result<(), SchemaValidationError> {
+        if let Some(max_row_size) = self.max_row_size {
+            if raw.len() > max_row_size {
+                return Err(SchemaValidationError::RowIsTooLarge {
+                    max_size: max_row_size,
+                    size: raw.len(),
+                });
+            }
+        }
+
+        self.validate_fields(raw)
+    }
+
+    /// Validates the fields only. Size is not checked, so it suits the JSON
+    /// with the TimeStamp written by the server as well
+    pub fn validate_fields(&self, raw: &[u8]) -> Result<(), SchemaValidationError> {
+        let mut found_fields = vec![false; self.fields.len()];
+
+        for line in JsonFirstLineReader::new(raw) {
+            let line = line.map_err(|_| SchemaValidationError::InvalidJson)?;
+            let name = line
+                .get_name()
+                .map_err(|_| SchemaValidationError::InvalidJson)?;
+
+            let index = match self.fields.iter().position(|field| field.name == name) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let field = &self.fields[index];
+
+            let value = match JsonFieldValue::from_raw(&raw[line.value_start..line.value_end]) {
+                Some(value) => value,
+                None => return Err(SchemaValidationError::InvalidJson),
+            };
+
+            if let JsonFieldValue::Null = value {
+                if field.required {
+                    return Err(SchemaValidationError::RequiredFieldIsNull {
+                        field: field.name.to_string(),
+                        position: line.value_start,
+                    });
+                }
+
+                continue;
+            }
+
+            if !field.field_type.matches(&value) {
+                return Err(SchemaValidationError::FieldTypeMismatch {
+                    field: field.name.to_string(),
+                    expected: field.field_type,
+                    position: line.value_start,
+                });
+            }
+
+            found_fields[index] = true;
+        }
+
+        for (field, found) in self.fields.iter().zip(found_fields) {
+            if field.required && !found {
+                return Err(SchemaValidationError::RequiredFieldIsMissing {
+                    field: field.name.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{test_utils::create_schema, validations::SchemaValidationError};
+
+    use super::{DbTableSchema, SchemaFieldType};
+
+    #[test]
+    fn test_valid_entity() {
+        let schema = create_schema();
+
+        let json = r#"{"PartitionKey":"pk","RowKey":"rk","Name":"test","Amount":5.5,"Active":true,"Tags":["a"],"Created":"2022-03-17T09:28:27","Other":{}}"#;
+
+        assert!(schema.validate(json.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let schema = create_schema();
+
+        let json = r#"{"PartitionKey":"pk","RowKey":"rk","Name":"test"}"#;
+
+        assert_eq!(
+            Err(SchemaValidationError::RequiredFieldIsMissing {
+                field: "Amount".to_string()
+            }),
+            schema.validate(json.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_field_type_mismatch() {
+        let schema = create_schema();
+
+        let json = r#"{"Name":"test","Amount":"5"}"#;
+
+        assert_eq!(
+            Err(SchemaValidationError::FieldTypeMismatch {
+                field: "Amount".to_string(),
+                expected: SchemaFieldType::Number,
+                position: 24,
+            }),
+            schema.validate(json.as_bytes())
+        );
+
+        let json = r#"{"Name":"test","Amount":5,"Created":"yesterday"}"#;
+
+        match schema.validate(json.as_bytes()) {
+            Err(SchemaValidationError::FieldTypeMismatch {
+                field, expected, ..
+            }) => {
+                assert_eq!("Created", field);
+                assert_eq!(SchemaFieldType::DateTime, expected);
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_null_fields() {
+        let schema = create_schema();
+
+        let json = r#"{"Name":"test","Amount":5,"Active":null}"#;
+        assert!(schema.validate(json.as_bytes()).is_ok());
+
+        let json = r#"{"Name":null,"Amount":5}"#;
+        assert_eq!(
+            Err(SchemaValidationError::RequiredFieldIsNull {
+                field: "Name".to_string(),
+                position: 8,
+            }),
+            schema.validate(json.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_row_is_too_large() {
+        let mut schema = DbTableSchema::new();
+        schema.max_row_size = Some(10);
+
+        assert_eq!(
+            Err(SchemaValidationError::RowIsTooLarge {
+                max_size: 10,
+                size: 17
+            }),
+            schema.validate(r#"{"Name":"tested"}"#.as_bytes())
+        );
+
+        assert!(schema
+            .validate_fields(r#"{"Name":"tested"}"#.as_bytes())
+            .is_ok());
+    }
+}
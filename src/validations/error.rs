@@ -1,4 +1,38 @@
+use super::SchemaFieldType;
+
 #[derive(Debug)]
 pub enum ValidationError {
     TableNameValidationError(String),
+    PartitionKeyValidationError(KeyValidationError),
+    RowKeyValidationError(KeyValidationError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyValidationError {
+    KeyIsEmpty,
+    KeyIsTooLong { max_length: usize, length: usize },
+    ForbiddenSymbol { symbol: char, position: usize },
+    InvalidEscapeSequence,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaValidationError {
+    InvalidJson,
+    RowIsTooLarge {
+        max_size: usize,
+        size: usize,
+    },
+    RequiredFieldIsMissing {
+        field: String,
+    },
+    RequiredFieldIsNull {
+        field: String,
+        position: usize,
+    },
+    /// Position is the byte offset of the value in the row JSON
+    FieldTypeMismatch {
+        field: String,
+        expected: SchemaFieldType,
+        position: usize,
+    },
 }
@@ -0,0 +1,177 @@
+use super::{KeyValidationError, ValidationError};
+
+#[derive(Debug, Clone)]
+pub struct KeyValidator {
+    /// Max length of the key in bytes
+    pub max_length: usize,
+    pub allow_empty: bool,
+    /// Control symbols (\0, \n, \t and etc) are rarely intended and break the key based APIs
+    pub allow_control_symbols: bool,
+    pub forbidden_symbols: Vec<char>,
+}
+
+impl KeyValidator {
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            allow_empty: false,
+            allow_control_symbols: false,
+            forbidden_symbols: Vec::new(),
+        }
+    }
+
+    pub fn validate(&self, key: &str) -> Result<(), KeyValidationError> {
+        if key.is_empty() {
+            if self.allow_empty {
+                return Ok(());
+            }
+
+            return Err(KeyValidationError::KeyIsEmpty);
+        }
+
+        if key.len() > self.max_length {
+            return Err(KeyValidationError::KeyIsTooLong {
+                max_length: self.max_length,
+                length: key.len(),
+            });
+        }
+
+        for (position, symbol) in key.char_indices() {
+            if !self.symbol_is_allowed(symbol) {
+                return Err(KeyValidationError::ForbiddenSymbol { symbol, position });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn symbol_is_allowed(&self, symbol: char) -> bool {
+        // Replacement character is what invalid UTF-8 becomes after a lossy conversion
+        if symbol == char::REPLACEMENT_CHARACTER {
+            return false;
+        }
+
+        if !self.allow_control_symbols && symbol.is_control() {
+            return false;
+        }
+
+        !self.forbidden_symbols.contains(&symbol)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbKeysValidator {
+    pub partition_key: KeyValidator,
+    pub row_key: KeyValidator,
+}
+
+impl DbKeysValidator {
+    pub fn create_default() -> Self {
+        Self {
+            partition_key: KeyValidator::new(255),
+            row_key: KeyValidator::new(1024),
+        }
+    }
+
+    pub fn validate_partition_key(&self, partition_key: &str) -> Result<(), ValidationError> {
+        self.partition_key
+            .validate(partition_key)
+            .map_err(ValidationError::PartitionKeyValidationError)
+    }
+
+    pub fn validate_row_key(&self, row_key: &str) -> Result<(), ValidationError> {
+        self.row_key
+            .validate(row_key)
+            .map_err(ValidationError::RowKeyValidationError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::validations::{KeyValidationError, ValidationError};
+
+    use super::{DbKeysValidator, KeyValidator};
+
+    #[test]
+    fn test_valid_key() {
+        let validator = KeyValidator::new(10);
+
+        assert!(validator.validate("my-key_1").is_ok());
+    }
+
+    #[test]
+    fn test_empty_key() {
+        let mut validator = KeyValidator::new(10);
+
+        assert_eq!(Err(KeyValidationError::KeyIsEmpty), validator.validate(""));
+
+        validator.allow_empty = true;
+
+        assert!(validator.validate("").is_ok());
+    }
+
+    #[test]
+    fn test_too_long_key() {
+        let validator = KeyValidator::new(3);
+
+        assert_eq!(
+            Err(KeyValidationError::KeyIsTooLong {
+                max_length: 3,
+                length: 4
+            }),
+            validator.validate("abcd")
+        );
+
+        // Length is in bytes
+        assert!(validator.validate("ab").is_ok());
+        assert!(validator.validate("aé").is_ok());
+        assert!(validator.validate("aéb").is_err());
+    }
+
+    #[test]
+    fn test_forbidden_symbols() {
+        let mut validator = KeyValidator::new(10);
+        validator.forbidden_symbols = vec!['/', '#'];
+
+        assert_eq!(
+            Err(KeyValidationError::ForbiddenSymbol {
+                symbol: '/',
+                position: 2
+            }),
+            validator.validate("ab/c")
+        );
+
+        assert_eq!(
+            Err(KeyValidationError::ForbiddenSymbol {
+                symbol: '\n',
+                position: 1
+            }),
+            validator.validate("a\nb")
+        );
+
+        assert!(validator.validate("a\u{FFFD}").is_err());
+
+        validator.allow_control_symbols = true;
+        assert!(validator.validate("a\nb").is_ok());
+    }
+
+    #[test]
+    fn test_keys_validator() {
+        let validator = DbKeysValidator::create_default();
+
+        match validator.validate_row_key("").unwrap_err() {
+            ValidationError::RowKeyValidationError(KeyValidationError::KeyIsEmpty) => {}
+            err => panic!("Unexpected validation error: {:?}", err),
+        }
+
+        match validator
+            .validate_partition_key(&"a".repeat(256))
+            .unwrap_err()
+        {
+            ValidationError::PartitionKeyValidationError(KeyValidationError::KeyIsTooLong {
+                ..
+            }) => {}
+            err => panic!("Unexpected validation error: {:?}", err),
+        }
+    }
+}
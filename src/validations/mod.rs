@@ -1,4 +1,8 @@
+mod db_table_schema;
 mod error;
+mod key_validator;
 mod validate_table_name;
-pub use error::ValidationError;
+pub use db_table_schema::{DbTableSchema, SchemaField, SchemaFieldType};
+pub use error::{KeyValidationError, SchemaValidationError, ValidationError};
+pub use key_validator::{DbKeysValidator, KeyValidator};
 pub use validate_table_name::validate_table_name;
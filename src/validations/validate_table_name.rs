@@ -101,6 +101,7 @@ mod test {
                 ValidationError::TableNameValidationError(reason) => {
                     println!("{}", reason);
                 }
+                err => panic!("Unexpected validation error: {:?}", err),
             }
         }
     }
@@ -118,6 +119,7 @@ mod test {
                 ValidationError::TableNameValidationError(reason) => {
                     println!("{}", reason);
                 }
+                err => panic!("Unexpected validation error: {:?}", err),
             }
         }
     }
@@ -135,6 +137,7 @@ mod test {
                 ValidationError::TableNameValidationError(reason) => {
                     println!("{}", reason);
                 }
+                err => panic!("Unexpected validation error: {:?}", err),
             }
         }
     }
@@ -152,6 +155,7 @@ mod test {
                 ValidationError::TableNameValidationError(reason) => {
                     println!("{}", reason);
                 }
+                err => panic!("Unexpected validation error: {:?}", err),
             }
         }
     }